@@ -0,0 +1,294 @@
+use std::fmt;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Serialize, Deserialize};
+
+const MONTH_NAMES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    if word.len() < 3 {
+        return None;
+    }
+    MONTH_NAMES.iter().position(|name| word.starts_with(name)).map(|i| i as u32 + 1)
+}
+
+// the first `weekday` after `today`, or today itself when `include_today` is set
+fn upcoming(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let mut days = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    if days == 0 && !include_today {
+        days = 7;
+    }
+    today + Days::new(days as u64)
+}
+
+// "3d", "2w", "1m", "1y" as well as "3 days", "2 weeks"...
+fn parse_offset(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.replace(' ', "");
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(split);
+    let amount: u32 = amount.parse().ok()?;
+    match unit {
+        "d" | "day" | "days" => today.checked_add_days(Days::new(amount as u64)),
+        "w" | "wk" | "wks" | "week" | "weeks" => today.checked_add_days(Days::new(amount as u64 * 7)),
+        "m" | "mo" | "month" | "months" => today.checked_add_months(Months::new(amount)),
+        "y" | "yr" | "year" | "years" => today.checked_add_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+// "oct 20", "20 oct", "october 20 2027"
+fn parse_month_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let (month, day, year) = match words {
+        [a, b] | [a, b, _] if parse_month(a).is_some() => (parse_month(a)?, b.parse::<u32>().ok()?, words.get(2)),
+        [a, b] | [a, b, _] if parse_month(b).is_some() => (parse_month(b)?, a.parse::<u32>().ok()?, words.get(2)),
+        _ => return None,
+    };
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year.parse().ok()?, month, day),
+        None => {
+            let this_year = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if this_year < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(this_year)
+            }
+        }
+    }
+}
+
+// parses absolute dates, weekdays and relative offsets like "tomorrow", "next monday" or "in 2w"
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    for format in ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(&input, format) {
+            return Some(date);
+        }
+    }
+    // words can be glued with dashes or underscores so they fit in one `due:` token
+    let normalized = input.replace(['-', '_'], " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] | ["now"] => Some(today),
+        ["tomorrow"] | ["tmr"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["next", "year"] => today.checked_add_months(Months::new(12)),
+        ["next", day] => Some(upcoming(today, parse_weekday(day)?, false)),
+        ["this", day] => Some(upcoming(today, parse_weekday(day)?, true)),
+        [day] if parse_weekday(day).is_some() => Some(upcoming(today, parse_weekday(day)?, true)),
+        ["in", rest @ ..] => parse_offset(&rest.join(" "), today),
+        [first, ..] if first.starts_with('+') => parse_offset(&normalized[1..], today),
+        _ => parse_month_day(&words, today).or_else(|| parse_offset(&normalized, today)),
+    }
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%a %Y-%m-%d").to_string().to_lowercase()
+}
+
+// how often a recurring task comes back; stored as its readable rule, e.g. "every 2 weeks"
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    MonthDay(u32),
+    Weekdays,
+}
+
+impl Recurrence {
+    // "daily", "weekly", "every 2 weeks", "monthly on day 15", "weekdays"...
+    pub fn parse(input: &str) -> Option<Recurrence> {
+        let normalized = input.trim().to_lowercase().replace(['-', '_'], " ");
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+        let unit = |amount: u32, unit: &str| match unit {
+            "day" | "days" | "d" => Some(Recurrence::Days(amount)),
+            "week" | "weeks" | "w" => Some(Recurrence::Weeks(amount)),
+            "month" | "months" | "m" => Some(Recurrence::Months(amount)),
+            "year" | "years" | "y" => Some(Recurrence::Months(amount.checked_mul(12)?)),
+            _ => None,
+        };
+        let recurrence = match words.as_slice() {
+            ["daily"] => Recurrence::Days(1),
+            ["weekly"] => Recurrence::Weeks(1),
+            ["biweekly"] | ["fortnightly"] => Recurrence::Weeks(2),
+            ["monthly"] => Recurrence::Months(1),
+            ["yearly"] | ["annually"] => Recurrence::Months(12),
+            ["weekdays"] | ["every", "weekday"] => Recurrence::Weekdays,
+            ["monthly", "on", "day", day] | ["every", "month", "on", "day", day] | ["monthly", "on", day] => {
+                Recurrence::MonthDay(day.trim_end_matches(|c: char| c.is_alphabetic()).parse().ok().filter(|day| (1..=31).contains(day))?)
+            },
+            ["every", name] => unit(1, name)?,
+            ["every", amount, name] => unit(amount.parse().ok().filter(|amount| *amount > 0)?, name)?,
+            [compact] => {
+                let split = compact.find(|c: char| !c.is_ascii_digit())?;
+                unit(compact[..split].parse().ok().filter(|amount| *amount > 0)?, &compact[split..])?
+            },
+            _ => return None,
+        };
+        Some(recurrence)
+    }
+
+    // the first date after `from` the task falls on again
+    pub fn next_after(self, from: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Days(n) => from.checked_add_days(Days::new(n as u64)),
+            Recurrence::Weeks(n) => from.checked_add_days(Days::new(n as u64 * 7)),
+            Recurrence::Months(n) => from.checked_add_months(Months::new(n)),
+            Recurrence::MonthDay(day) => {
+                // months shorter than `day` use their last day instead
+                let this_month = from.with_day(day.min(last_day(from)?))?;
+                if this_month > from {
+                    return Some(this_month);
+                }
+                let next_month = from.with_day(1)?.checked_add_months(Months::new(1))?;
+                next_month.with_day(day.min(last_day(next_month)?))
+            },
+            Recurrence::Weekdays => {
+                let mut next = from.succ_opt()?;
+                while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+                    next = next.succ_opt()?;
+                }
+                Some(next)
+            },
+        }
+    }
+}
+
+fn last_day(date: NaiveDate) -> Option<u32> {
+    let first = date.with_day(1)?;
+    Some((first.checked_add_months(Months::new(1))? - first).num_days() as u32)
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Days(1) => write!(f, "daily"),
+            Recurrence::Weeks(1) => write!(f, "weekly"),
+            Recurrence::Months(1) => write!(f, "monthly"),
+            Recurrence::Months(12) => write!(f, "yearly"),
+            Recurrence::Days(n) => write!(f, "every {} days", n),
+            Recurrence::Weeks(n) => write!(f, "every {} weeks", n),
+            Recurrence::Months(n) => write!(f, "every {} months", n),
+            Recurrence::MonthDay(day) => write!(f, "monthly on day {}", day),
+            Recurrence::Weekdays => write!(f, "weekdays"),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Recurrence, String> {
+        Recurrence::parse(&rule).ok_or_else(|| format!("unknown recurrence \"{}\"", rule))
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> String {
+        recurrence.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn relative_dates() {
+        assert_eq!(parse_date("today", today()), date(2026, 10, 14));
+        assert_eq!(parse_date("tomorrow", today()), date(2026, 10, 15));
+        assert_eq!(parse_date("yesterday", today()), date(2026, 10, 13));
+        assert_eq!(parse_date("in 3 days", today()), date(2026, 10, 17));
+        assert_eq!(parse_date("+2w", today()), date(2026, 10, 28));
+        assert_eq!(parse_date("in-1m", today()), date(2026, 11, 14));
+        assert_eq!(parse_date("next year", today()), date(2027, 10, 14));
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_eq!(parse_date("2026-12-01", today()), date(2026, 12, 1));
+        assert_eq!(parse_date("01.12.2026", today()), date(2026, 12, 1));
+        assert_eq!(parse_date("oct 20", today()), date(2026, 10, 20));
+        // a day already gone this year means next year's
+        assert_eq!(parse_date("20 jan", today()), date(2027, 1, 20));
+        assert_eq!(parse_date("feb 30", today()), None);
+    }
+
+    #[test]
+    fn weekdays_roll_over_to_next_week() {
+        assert_eq!(parse_date("friday", today()), date(2026, 10, 16));
+        assert_eq!(parse_date("monday", today()), date(2026, 10, 19));
+        assert_eq!(parse_date("wednesday", today()), date(2026, 10, 14));
+        assert_eq!(parse_date("this wed", today()), date(2026, 10, 14));
+        assert_eq!(parse_date("next wed", today()), date(2026, 10, 21));
+        assert_eq!(parse_date("next-sunday", today()), date(2026, 10, 18));
+    }
+
+    #[test]
+    fn huge_offsets_are_rejected_instead_of_overflowing() {
+        assert_eq!(parse_date("+999999999y", today()), None);
+        assert_eq!(parse_date("in 4294967295 years", today()), None);
+        assert_eq!(parse_date("in 99999999999 days", today()), None);
+        assert_eq!(parse_date("someday", today()), None);
+    }
+
+    #[test]
+    fn recurrence_rules() {
+        assert_eq!(Recurrence::parse("weekly"), Some(Recurrence::Weeks(1)));
+        assert_eq!(Recurrence::parse("every-2-weeks"), Some(Recurrence::Weeks(2)));
+        assert_eq!(Recurrence::parse("3d"), Some(Recurrence::Days(3)));
+        assert_eq!(Recurrence::parse("every 2 years"), Some(Recurrence::Months(24)));
+        assert_eq!(Recurrence::parse("monthly on day 15th"), Some(Recurrence::MonthDay(15)));
+        assert_eq!(Recurrence::parse("every 0 days"), None);
+        assert_eq!(Recurrence::parse("monthly on day 32"), None);
+        assert_eq!(Recurrence::parse("every-999999999-years"), None);
+    }
+
+    #[test]
+    fn rules_read_back_from_their_text() {
+        for rule in [Recurrence::Days(1), Recurrence::Weeks(3), Recurrence::Months(12), Recurrence::MonthDay(31), Recurrence::Weekdays] {
+            assert_eq!(Recurrence::parse(&rule.to_string()), Some(rule));
+        }
+    }
+
+    #[test]
+    fn month_day_falls_back_to_the_last_day_of_short_months() {
+        let rule = Recurrence::MonthDay(31);
+        assert_eq!(rule.next_after(date(2026, 1, 31).unwrap()), date(2026, 2, 28));
+        assert_eq!(rule.next_after(date(2028, 1, 31).unwrap()), date(2028, 2, 29));
+        assert_eq!(rule.next_after(date(2026, 2, 28).unwrap()), date(2026, 3, 31));
+        assert_eq!(rule.next_after(date(2026, 4, 10).unwrap()), date(2026, 4, 30));
+        assert_eq!(Recurrence::MonthDay(15).next_after(date(2026, 12, 20).unwrap()), date(2027, 1, 15));
+    }
+
+    #[test]
+    fn weekdays_skip_the_weekend() {
+        // friday to monday
+        assert_eq!(Recurrence::Weekdays.next_after(date(2026, 10, 16).unwrap()), date(2026, 10, 19));
+        assert_eq!(Recurrence::Weekdays.next_after(today()), date(2026, 10, 15));
+    }
+}
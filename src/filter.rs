@@ -0,0 +1,420 @@
+use chrono::{DateTime, Local, NaiveDate};
+use crate::{Priority, Task, dates, open_dependencies, parse_project, parse_tag};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Open,
+    Done,
+    Blocked,
+    Ready,
+    Overdue,
+}
+
+impl Status {
+    fn parse(name: &str) -> Option<Status> {
+        match name.to_lowercase().as_str() {
+            "open" | "pending" => Some(Status::Open),
+            "done" | "completed" => Some(Status::Done),
+            "blocked" | "waiting" => Some(Status::Blocked),
+            "ready" => Some(Status::Ready),
+            "overdue" => Some(Status::Overdue),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    fn holds(self, left: i64, right: i64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+        }
+    }
+}
+
+// a parsed filter expression; `None` values stand for "has no tags/project/priority/due date"
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Text(String),
+    Tag(Option<String>),
+    Project(Option<String>),
+    Priority(Option<Priority>),
+    Due(Option<NaiveDate>),
+    DueBefore(NaiveDate),
+    DueAfter(NaiveDate),
+    Status(Status),
+    // age in days since the task was created
+    Age(Comparison, i64),
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+}
+
+// what a filter needs beyond the task itself
+pub struct Context<'a> {
+    // the open tasks, to tell blocked tasks from ready ones
+    pub tasks: &'a [Task],
+    pub today: NaiveDate,
+    // whether text also matches notes and annotations
+    pub all_fields: bool,
+}
+
+impl Filter {
+    pub fn matches(&self, task: &Task, context: &Context) -> bool {
+        match self {
+            Filter::Text(text) => {
+                task.description.to_lowercase().contains(text)
+                    || task.tags.iter().any(|tag| tag.contains(text))
+                    || context.all_fields && task.notes.as_ref().is_some_and(|notes| notes.to_lowercase().contains(text))
+                    || context.all_fields && task.annotations.iter().any(|annotation| annotation.text.to_lowercase().contains(text))
+            },
+            Filter::Tag(Some(tag)) => task.has_tag(tag),
+            Filter::Tag(None) => task.tags.is_empty(),
+            Filter::Project(project) => task.project == *project,
+            Filter::Priority(priority) => task.priority == *priority,
+            Filter::Due(due) => task.due == *due,
+            Filter::DueBefore(date) => task.due.is_some_and(|due| due < *date),
+            Filter::DueAfter(date) => task.due.is_some_and(|due| due > *date),
+            Filter::Status(status) => {
+                let open = task.completed_at.is_none();
+                match status {
+                    Status::Open => open,
+                    Status::Done => !open,
+                    Status::Blocked => open && !open_dependencies(task, context.tasks).is_empty(),
+                    Status::Ready => open && open_dependencies(task, context.tasks).is_empty(),
+                    Status::Overdue => open && task.due.is_some_and(|due| due < context.today),
+                }
+            },
+            Filter::Age(comparison, days) => {
+                let created = DateTime::from_timestamp(task.created_at, 0).map(|time| time.with_timezone(&Local).date_naive());
+                created.is_some_and(|created| comparison.holds((context.today - created).num_days(), *days))
+            },
+            Filter::Not(filter) => !filter.matches(task, context),
+            Filter::And(filters) => filters.iter().all(|filter| filter.matches(task, context)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.matches(task, context)),
+        }
+    }
+
+    // completed tasks only need loading when the filter asks about them
+    pub fn wants_archive(&self) -> bool {
+        self.asks_for_done(false)
+    }
+
+    // `status:done` asks for them, and so does a negated status of open work
+    // like `NOT status:open`
+    fn asks_for_done(&self, negated: bool) -> bool {
+        match self {
+            Filter::Status(Status::Done) => !negated,
+            Filter::Status(_) => negated,
+            Filter::Not(filter) => filter.asks_for_done(!negated),
+            Filter::And(filters) | Filter::Or(filters) => filters.iter().any(|filter| filter.asks_for_done(negated)),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    // a fully quoted phrase, always matched as text
+    Phrase(String),
+}
+
+// splits the expression into words, parentheses and quoted phrases,
+// remembering which character each token started at for error messages
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push((start, if c == '(' { Token::Open } else { Token::Close }));
+            continue;
+        }
+        let mut word = String::new();
+        let mut quoted_only = true;
+        while let Some(&(position, c)) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            chars.next();
+            if c != '"' {
+                word.push(c);
+                quoted_only = false;
+                continue;
+            }
+            // `"exact phrase"` or a quoted value like `due.before:"next monday"`
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, c)) => word.push(c),
+                    None => return Err(format!("unterminated quote at position {}", position + 1)),
+                }
+            }
+        }
+        tokens.push((start, if quoted_only { Token::Phrase(word) } else { Token::Word(word) }));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    today: NaiveDate,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map(|(start, _)| start + 1).unwrap_or(0)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(word)) if word == keyword) {
+            self.next += 1;
+            return true;
+        }
+        false
+    }
+
+    // or := and ("OR" and)*
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut filters = vec![self.parse_and()?];
+        while self.eat_keyword("OR") {
+            filters.push(self.parse_and()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    // and := unary (["AND"] unary)*, terms next to each other must all match
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut filters = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Token::Close) => break,
+                Some(Token::Word(word)) if word == "OR" => break,
+                _ => {},
+            }
+            let position = self.position();
+            if self.eat_keyword("AND") && matches!(self.peek(), None | Some(Token::Close)) {
+                return Err(format!("expected a filter after AND at position {}", position));
+            }
+            filters.push(self.parse_unary()?);
+        }
+        match filters.len() {
+            0 => Err(match self.peek() {
+                Some(_) => format!("expected a filter at position {}", self.position()),
+                None => "expected a filter at the end of the expression".to_string(),
+            }),
+            1 => Ok(filters.remove(0)),
+            _ => Ok(Filter::And(filters)),
+        }
+    }
+
+    // unary := ("NOT" | "-") unary | "(" or ")" | term
+    fn parse_unary(&mut self) -> Result<Filter, String> {
+        let position = self.position();
+        let token = match self.tokens.get(self.next) {
+            Some((_, token)) => token.clone(),
+            None => return Err("expected a filter at the end of the expression".to_string()),
+        };
+        self.next += 1;
+        match token {
+            Token::Word(word) if word == "NOT" || word == "-" => Ok(Filter::Not(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let filter = self.parse_or()?;
+                match self.peek() {
+                    Some(Token::Close) => {
+                        self.next += 1;
+                        Ok(filter)
+                    },
+                    _ => Err(format!("missing ')' for the '(' at position {}", position)),
+                }
+            },
+            Token::Close => Err(format!("unexpected ')' at position {}", position)),
+            Token::Phrase(text) => Ok(Filter::Text(text.to_lowercase())),
+            Token::Word(word) => self.parse_term(&word),
+        }
+    }
+
+    fn parse_date(&self, value: &str) -> Result<NaiveDate, String> {
+        dates::parse_date(value, self.today).ok_or_else(|| format!("could not understand the date \"{}\"", value))
+    }
+
+    fn parse_term(&self, word: &str) -> Result<Filter, String> {
+        if let Some(rest) = word.strip_prefix('-').filter(|rest| !rest.is_empty()) {
+            return Ok(Filter::Not(Box::new(self.parse_term(rest)?)));
+        }
+        if word.starts_with('+') {
+            return parse_tag(word).map(|tag| Filter::Tag(Some(tag))).ok_or_else(|| format!("invalid tag \"{}\"", word));
+        }
+        if let Some(rest) = word.strip_prefix("age") {
+            if let Some(filter) = self.parse_age(rest)? {
+                return Ok(filter);
+            }
+        }
+        let Some((key, value)) = word.split_once(':') else {
+            return Ok(Filter::Text(word.to_lowercase()));
+        };
+        let none = value.eq_ignore_ascii_case("none");
+        let filter = match key.to_lowercase().as_str() {
+            "tag" | "tags" if none => Filter::Tag(None),
+            "tag" | "tags" => Filter::Tag(Some(parse_tag(&format!("+{}", value)).ok_or_else(|| format!("invalid tag \"{}\"", value))?)),
+            "project" | "proj" if none => Filter::Project(None),
+            "project" | "proj" => Filter::Project(Some(parse_project(value).ok_or_else(|| format!("invalid project name \"{}\"", value))?)),
+            "pri" | "priority" if none => Filter::Priority(None),
+            "pri" | "priority" => Filter::Priority(Some(Priority::parse(value).ok_or_else(|| format!("unknown priority \"{}\", expected H, M, L or none", value))?)),
+            "due" if none => Filter::Due(None),
+            "due" if value.eq_ignore_ascii_case("any") => Filter::Not(Box::new(Filter::Due(None))),
+            "due" => Filter::Due(Some(self.parse_date(value)?)),
+            "due.before" | "due.by" => Filter::DueBefore(self.parse_date(value)?),
+            "due.after" => Filter::DueAfter(self.parse_date(value)?),
+            "status" | "is" => Filter::Status(Status::parse(value).ok_or_else(|| format!("unknown status \"{}\", expected open, done, blocked, ready or overdue", value))?),
+            "text" | "description" => Filter::Text(value.to_lowercase()),
+            // things like urls are plain text, but a mistyped key should not silently match nothing
+            key if key.is_empty() || !key.chars().all(|c| c.is_alphabetic() || c == '.') || value.starts_with('/') => Filter::Text(word.to_lowercase()),
+            key => return Err(format!("unknown filter \"{}:\", put it in quotes to search for the text", key)),
+        };
+        Ok(filter)
+    }
+
+    // ">7d", "<=2w", "=0d" after the word "age"
+    fn parse_age(&self, rest: &str) -> Result<Option<Filter>, String> {
+        let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+            (Comparison::GreaterOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix("<=") {
+            (Comparison::LessOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix('>') {
+            (Comparison::Greater, value)
+        } else if let Some(value) = rest.strip_prefix('<') {
+            (Comparison::Less, value)
+        } else if let Some(value) = rest.strip_prefix('=') {
+            (Comparison::Equal, value)
+        } else {
+            return Ok(None);
+        };
+        // "7d" is read as a date that many days from now, bare numbers count days
+        let value = if value.chars().all(|c| c.is_ascii_digit()) { format!("{}d", value) } else { value.to_string() };
+        let date = dates::parse_date(&format!("in {}", value), self.today).ok_or_else(|| format!("could not understand the age \"{}\", try 7d, 2w or 3m", value))?;
+        Ok(Some(Filter::Age(comparison, (date - self.today).num_days())))
+    }
+}
+
+// parses expressions like `tag:work due.before:friday -someday (pri:H OR status:overdue)`;
+// an empty expression matches every task
+pub fn parse(input: &str, today: NaiveDate) -> Result<Filter, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(Filter::And(vec![]));
+    }
+    let mut parser = Parser { tokens, next: 0, today };
+    let filter = parser.parse_or()?;
+    match parser.peek() {
+        Some(_) => Err(format!("unexpected ')' at position {}", parser.position())),
+        None => Ok(filter),
+    }
+}
+
+// rebuilds the expression from command line words; a word the shell received
+// quoted, like "exact phrase", stays one phrase unless it is itself an expression
+pub fn from_args(args: &[String], today: NaiveDate) -> Result<Filter, String> {
+    let words: Vec<String> = args.iter().map(|arg| {
+        let expression = arg.contains([':', '(', ')', '"', '<', '>', '=']) || arg.split_whitespace().any(|word| {
+            ["AND", "OR", "NOT"].contains(&word) || word.starts_with(['-', '+'])
+        });
+        if arg.contains(char::is_whitespace) && !expression {
+            format!("\"{}\"", arg)
+        } else {
+            arg.clone()
+        }
+    }).collect();
+    parse(&words.join(" "), today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap()
+    }
+
+    fn text(word: &str) -> Filter {
+        Filter::Text(word.to_string())
+    }
+
+    fn not(filter: Filter) -> Filter {
+        Filter::Not(Box::new(filter))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parse("a OR b c", today()), Ok(Filter::Or(vec![text("a"), Filter::And(vec![text("b"), text("c")])])));
+        assert_eq!(parse("a AND b OR c", today()), Ok(Filter::Or(vec![Filter::And(vec![text("a"), text("b")]), text("c")])));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term_only() {
+        assert_eq!(parse("NOT a b", today()), Ok(Filter::And(vec![not(text("a")), text("b")])));
+        assert_eq!(parse("-a b", today()), Ok(Filter::And(vec![not(text("a")), text("b")])));
+        assert_eq!(parse("NOT (a OR b)", today()), Ok(not(Filter::Or(vec![text("a"), text("b")]))));
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(parse("(a OR b) c", today()), Ok(Filter::And(vec![Filter::Or(vec![text("a"), text("b")]), text("c")])));
+    }
+
+    #[test]
+    fn quotes_keep_phrases_and_values_together() {
+        assert_eq!(parse("\"exact OR phrase\"", today()), Ok(text("exact or phrase")));
+        let monday = dates::parse_date("next monday", today()).unwrap();
+        assert_eq!(parse("due.before:\"next monday\"", today()), Ok(Filter::DueBefore(monday)));
+        let args = vec!["exact phrase".to_string(), "tag:work".to_string()];
+        assert_eq!(from_args(&args, today()), Ok(Filter::And(vec![text("exact phrase"), Filter::Tag(Some("work".to_string()))])));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(parse("a (b", today()), Err("missing ')' for the '(' at position 3".to_string()));
+        assert_eq!(parse("a )", today()), Err("unexpected ')' at position 3".to_string()));
+        assert_eq!(parse("a \"b", today()), Err("unterminated quote at position 3".to_string()));
+        assert_eq!(parse("a AND", today()), Err("expected a filter after AND at position 3".to_string()));
+        // positions count characters, not bytes
+        assert_eq!(parse("café (b", today()), Err("missing ')' for the '(' at position 6".to_string()));
+        assert_eq!(parse("\"naïve\" \"b", today()), Err("unterminated quote at position 9".to_string()));
+        assert!(parse("colour:red", today()).unwrap_err().contains("unknown filter \"colour:\""));
+    }
+
+    #[test]
+    fn archive_is_loaded_for_done_tasks_and_negated_open_ones() {
+        let wants = |input: &str| parse(input, today()).unwrap().wants_archive();
+        assert!(wants("status:done"));
+        assert!(wants("NOT status:open"));
+        assert!(wants("-status:blocked tag:x"));
+        assert!(wants("a OR NOT NOT status:done"));
+        assert!(!wants("NOT status:done"));
+        assert!(!wants("status:open -tag:x"));
+    }
+}
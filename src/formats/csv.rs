@@ -0,0 +1,188 @@
+use ::csv::{ReaderBuilder, Writer};
+use crate::{Annotation, Priority, Task, dates::Recurrence, parse_project};
+use super::{Parsed, add_tag, format_timestamp, parse_date, parse_timestamp};
+
+// the documented column set, in export order
+const COLUMNS: [&str; 15] = [
+    "id", "description", "status", "created_at", "updated_at", "completed_at", "due", "priority",
+    "tags", "project", "parent", "depends_on", "recur", "notes", "annotations",
+];
+
+// header names other spreadsheets use for the same columns
+fn column_for(header: &str) -> Option<&'static str> {
+    let header = header.trim().to_lowercase().replace([' ', '-'], "_");
+    let column = match header.as_str() {
+        "title" | "task" | "name" | "summary" | "content" => "description",
+        "state" | "done" => "status",
+        "created" | "date_added" | "added" => "created_at",
+        "updated" | "modified" | "last_modified" => "updated_at",
+        "completed" | "done_at" | "finished" => "completed_at",
+        "due_date" | "deadline" => "due",
+        "pri" => "priority",
+        "tag" | "labels" | "categories" => "tags",
+        "list" => "project",
+        "depends" | "dependencies" | "blocked_by" => "depends_on",
+        "recurrence" | "repeat" | "recurs" => "recur",
+        "note" | "details" => "notes",
+        "annotation" | "comments" => "annotations",
+        other => COLUMNS.iter().find(|column| **column == other)?,
+    };
+    Some(column)
+}
+
+fn join_ids(ids: &[u32]) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+pub fn write(tasks: &[Task]) -> Result<String, String> {
+    let mut writer = Writer::from_writer(Vec::new());
+    writer.write_record(COLUMNS).map_err(|e| e.to_string())?;
+    for task in tasks {
+        let annotations: Vec<String> = task.annotations.iter().map(|annotation| format!("{} {}", format_timestamp(annotation.at), annotation.text)).collect();
+        writer.write_record([
+            task.id.to_string(),
+            task.description.clone(),
+            if task.completed_at.is_some() { "done" } else { "open" }.to_string(),
+            format_timestamp(task.created_at),
+            task.updated_at.map(format_timestamp).unwrap_or_default(),
+            task.completed_at.map(format_timestamp).unwrap_or_default(),
+            task.due.map(|due| due.to_string()).unwrap_or_default(),
+            task.priority.map(|priority| priority.letter().to_string()).unwrap_or_default(),
+            task.tags.join(" "),
+            task.project.clone().unwrap_or_default(),
+            task.parent.map(|parent| parent.to_string()).unwrap_or_default(),
+            join_ids(&task.depends_on),
+            task.recur.map(|recur| recur.to_string()).unwrap_or_default(),
+            task.notes.clone().unwrap_or_default(),
+            annotations.join("\n"),
+        ]).map_err(|e| e.to_string())?;
+    }
+    let data = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(data).map_err(|e| e.to_string())
+}
+
+fn is_done(status: &str) -> Option<bool> {
+    match status.trim().to_lowercase().as_str() {
+        "" | "open" | "pending" | "todo" | "no" | "false" | "0" => Some(false),
+        "done" | "completed" | "complete" | "x" | "yes" | "true" | "1" => Some(true),
+        _ => None,
+    }
+}
+
+// columns are matched by their header, so they can come in any order
+pub fn read(text: &str) -> Result<Parsed, String> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(|e| format!("could not read the csv header: {}", e))?.clone();
+    let mut warnings = Vec::new();
+    let columns: Vec<Option<&str>> = headers.iter().map(|header| {
+        let column = column_for(header);
+        if column.is_none() && !header.trim().is_empty() {
+            warnings.push(format!("ignored unknown column \"{}\"", header));
+        }
+        column
+    }).collect();
+    if !columns.contains(&Some("description")) {
+        return Err(format!("the csv needs a description column, found: {}", headers.iter().collect::<Vec<&str>>().join(", ")));
+    }
+    let mut tasks = Vec::new();
+    let mut skipped = 0;
+    for (row, record) in reader.records().enumerate() {
+        // the header is line 1
+        let line = row + 2;
+        let record = record.map_err(|e| format!("line {}: {}", line, e))?;
+        let value = |name: &str| columns.iter().position(|column| *column == Some(name)).and_then(|i| record.get(i)).map(str::trim).filter(|value| !value.is_empty());
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", line, message));
+        let Some(description) = value("description") else {
+            warn("no description, skipped".to_string());
+            skipped += 1;
+            continue;
+        };
+        let mut task = Task::new(0, description.to_string());
+        if let Some(id) = value("id") {
+            match id.trim_start_matches('#').parse() {
+                Ok(id) => task.id = id,
+                Err(_) => warn(format!("ignored id \"{}\"", id)),
+            }
+        }
+        if let Some(created) = value("created_at") {
+            match parse_timestamp(created) {
+                Some(created) => task.created_at = created,
+                None => warn(format!("could not understand created_at \"{}\"", created)),
+            }
+        }
+        task.updated_at = value("updated_at").and_then(parse_timestamp);
+        task.completed_at = value("completed_at").and_then(parse_timestamp);
+        match value("status").map(|status| (status, is_done(status))) {
+            Some((_, Some(true))) if task.completed_at.is_none() => task.completed_at = task.updated_at.or(Some(task.created_at)),
+            Some((_, Some(false))) => task.completed_at = None,
+            Some((status, None)) => warn(format!("unknown status \"{}\", imported as open", status)),
+            _ => {},
+        }
+        if let Some(due) = value("due") {
+            match parse_date(due) {
+                Some(due) => task.due = Some(due),
+                None => warn(format!("could not understand the due date \"{}\"", due)),
+            }
+        }
+        if let Some(priority) = value("priority") {
+            match Priority::parse(priority) {
+                Some(priority) => task.priority = Some(priority),
+                None => warn(format!("unknown priority \"{}\"", priority)),
+            }
+        }
+        for tag in value("tags").unwrap_or_default().split([' ', ',', ';']).filter(|tag| !tag.is_empty()) {
+            add_tag(&mut task, tag, &mut warn);
+        }
+        if let Some(project) = value("project") {
+            match parse_project(&project.replace(' ', "-")) {
+                Some(project) => task.project = Some(project),
+                None => warn(format!("invalid project name \"{}\"", project)),
+            }
+        }
+        task.parent = value("parent").and_then(|parent| parent.trim_start_matches('#').parse().ok());
+        task.depends_on = value("depends_on").unwrap_or_default().split([' ', ',']).filter_map(|id| id.trim_start_matches('#').parse().ok()).collect();
+        if let Some(recur) = value("recur") {
+            match Recurrence::parse(recur) {
+                Some(recur) => task.recur = Some(recur),
+                None => warn(format!("unknown recurrence \"{}\"", recur)),
+            }
+        }
+        task.notes = value("notes").map(str::to_string);
+        for line in value("annotations").unwrap_or_default().lines().filter(|line| !line.trim().is_empty()) {
+            // "<timestamp> <text>", or just text stamped with the creation time
+            let stamped = line.split_once(' ').filter(|(at, _)| at.contains('-')).and_then(|(at, text)| Some((parse_timestamp(at)?, text)));
+            task.annotations.push(match stamped {
+                Some((at, text)) => Annotation { at, text: text.to_string() },
+                None => Annotation { at: task.created_at, text: line.to_string() },
+            });
+        }
+        tasks.push(task);
+    }
+    Ok(Parsed { tasks, warnings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use super::*;
+    use crate::formats::tests::{assert_same, sample};
+
+    #[test]
+    fn round_trip_keeps_every_field() {
+        let tasks = sample();
+        let parsed = read(&write(&tasks).unwrap()).unwrap();
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_same(&parsed.tasks, &tasks);
+    }
+
+    #[test]
+    fn other_spreadsheets_columns_are_recognised() {
+        let parsed = read("Title,Done,Due Date,Labels,Colour\nwater plants,yes,2026-10-05,garden #home,green\n").unwrap();
+        let task = &parsed.tasks[0];
+        assert_eq!(task.description, "water plants");
+        assert!(task.completed_at.is_some());
+        assert_eq!(task.due, NaiveDate::from_ymd_opt(2026, 10, 5));
+        assert_eq!(task.tags, ["garden", "home"]);
+        assert_eq!(parsed.warnings, ["ignored unknown column \"Colour\""]);
+    }
+}
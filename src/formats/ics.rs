@@ -0,0 +1,357 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use crate::{Annotation, Priority, Task, dates::Recurrence, parse_project};
+use super::{Parsed, add_tag, format_timestamp, parse_timestamp};
+
+// the uid stays the same for as long as the task exists, so calendar apps
+// update their copy instead of adding another
+fn uid(id: u32) -> String {
+    format!("taskz-{}", id)
+}
+
+fn format_utc(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.format("%Y%m%dT%H%M%SZ").to_string()).unwrap_or_default()
+}
+
+// RFC 5545 priorities run from 1 (highest) to 9, 0 being none
+fn ical_priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+fn rrule(recur: Recurrence) -> String {
+    match recur {
+        Recurrence::Days(n) => format!("FREQ=DAILY;INTERVAL={}", n),
+        Recurrence::Weeks(n) => format!("FREQ=WEEKLY;INTERVAL={}", n),
+        Recurrence::Months(n) if n % 12 == 0 => format!("FREQ=YEARLY;INTERVAL={}", n / 12),
+        Recurrence::Months(n) => format!("FREQ=MONTHLY;INTERVAL={}", n),
+        Recurrence::MonthDay(day) => format!("FREQ=MONTHLY;BYMONTHDAY={}", day),
+        Recurrence::Weekdays => "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {},
+        }
+    }
+    result
+}
+
+// content lines are folded at 75 bytes, without splitting a character
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+pub fn write(tasks: &[Task]) -> String {
+    let now = Utc::now().timestamp();
+    let mut lines = vec!["BEGIN:VCALENDAR".to_string(), "VERSION:2.0".to_string(), "PRODID:-//taskz//taskz//EN".to_string()];
+    for task in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}", uid(task.id)));
+        lines.push(format!("DTSTAMP:{}", format_utc(now)));
+        lines.push(format!("CREATED:{}", format_utc(task.created_at)));
+        lines.extend(task.updated_at.map(|updated| format!("LAST-MODIFIED:{}", format_utc(updated))));
+        lines.push(format!("SUMMARY:{}", escape(&task.description)));
+        lines.extend(task.notes.as_ref().map(|notes| format!("DESCRIPTION:{}", escape(notes))));
+        lines.extend(task.due.map(|due| format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d"))));
+        lines.extend(task.priority.map(|priority| format!("PRIORITY:{}", ical_priority(priority))));
+        match task.completed_at {
+            Some(completed) => {
+                lines.push("STATUS:COMPLETED".to_string());
+                lines.push(format!("COMPLETED:{}", format_utc(completed)));
+            },
+            None => lines.push("STATUS:NEEDS-ACTION".to_string()),
+        }
+        if !task.tags.is_empty() {
+            lines.push(format!("CATEGORIES:{}", task.tags.iter().map(|tag| escape(tag)).collect::<Vec<String>>().join(",")));
+        }
+        lines.extend(task.recur.map(|recur| format!("RRULE:{}", rrule(recur))));
+        lines.extend(task.parent.map(|parent| format!("RELATED-TO;RELTYPE=PARENT:{}", uid(parent))));
+        lines.extend(task.depends_on.iter().map(|id| format!("RELATED-TO;RELTYPE=DEPENDS-ON:{}", uid(*id))));
+        lines.extend(task.annotations.iter().map(|annotation| format!("COMMENT:{}", escape(&format!("{} {}", format_timestamp(annotation.at), annotation.text)))));
+        // icalendar has no projects, so it goes into an extension property
+        lines.extend(task.project.as_ref().map(|project| format!("X-TASKZ-PROJECT:{}", escape(project))));
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut out = String::new();
+    for line in lines {
+        fold(&line, &mut out);
+    }
+    out
+}
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+// `NAME;PARAM=value;PARAM="quoted:value":the value`
+fn parse_property(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let colon = line.char_indices().find(|(_, c)| {
+        if *c == '"' {
+            quoted = !quoted;
+        }
+        *c == ':' && !quoted
+    })?.0;
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    let params = parts.filter_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        Some((key.trim().to_uppercase(), value.trim_matches('"').to_string()))
+    }).collect();
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+// UTC ("...Z"), floating local times and plain dates
+fn parse_datetime(property: &Property) -> Option<i64> {
+    let value = property.value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok().map(|time| time.and_utc().timestamp());
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))?;
+    Local.from_local_datetime(&local).earliest().map(|time| time.timestamp())
+}
+
+fn parse_due(property: &Property) -> Option<NaiveDate> {
+    // a date-only due is taken as is, times are read in local time
+    NaiveDate::parse_from_str(property.value.trim(), "%Y%m%d").ok()
+        .or_else(|| DateTime::from_timestamp(parse_datetime(property)?, 0).map(|time| time.with_timezone(&Local).date_naive()))
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.trim().parse::<u8>().ok()? {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_rrule(value: &str) -> Option<Recurrence> {
+    let parts: Vec<(&str, &str)> = value.split(';').filter_map(|part| part.split_once('=')).collect();
+    let get = |key: &str| parts.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, value)| *value);
+    let interval = match get("INTERVAL") {
+        Some(interval) => interval.parse().ok().filter(|n| *n > 0)?,
+        None => 1,
+    };
+    // anything narrower than these (counts, end dates, other days) has no taskz equivalent
+    if parts.iter().any(|(name, _)| !["FREQ", "INTERVAL", "BYDAY", "BYMONTHDAY", "WKST"].contains(&name.to_uppercase().as_str())) {
+        return None;
+    }
+    match (get("FREQ")?.to_uppercase().as_str(), get("BYDAY"), get("BYMONTHDAY")) {
+        ("DAILY", None, None) => Some(Recurrence::Days(interval)),
+        ("WEEKLY", None, None) => Some(Recurrence::Weeks(interval)),
+        ("WEEKLY", Some(days), None) if interval == 1 && days.eq_ignore_ascii_case("MO,TU,WE,TH,FR") => Some(Recurrence::Weekdays),
+        ("MONTHLY", None, None) => Some(Recurrence::Months(interval)),
+        ("MONTHLY", None, Some(day)) if interval == 1 => day.parse().ok().filter(|day| (1..=31).contains(day)).map(Recurrence::MonthDay),
+        ("YEARLY", None, None) => interval.checked_mul(12).map(Recurrence::Months),
+        _ => None,
+    }
+}
+
+// lines starting with a space or tab continue the one before; each comes
+// with the line number it started on
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+fn parse_todo(properties: &[Property], ids: &[String], warn: &mut dyn FnMut(String)) -> Option<Task> {
+    let get = |name: &str| properties.iter().find(|property| property.name == name);
+    let Some(summary) = get("SUMMARY").map(|summary| unescape(&summary.value)).filter(|summary| !summary.trim().is_empty()) else {
+        warn("no SUMMARY, skipped".to_string());
+        return None;
+    };
+    // ids are positions in the file, so RELATED-TO can point at any uid
+    let id_for = |uid: &str| ids.iter().position(|other| !uid.is_empty() && other == uid).map(|i| i as u32 + 1);
+    let mut task = Task::new(get("UID").and_then(|uid| id_for(&uid.value)).unwrap_or(0), summary.trim().to_string());
+    if let Some(created) = get("CREATED").or_else(|| get("DTSTAMP")).and_then(parse_datetime) {
+        task.created_at = created;
+    }
+    task.updated_at = get("LAST-MODIFIED").and_then(parse_datetime);
+    task.notes = get("DESCRIPTION").map(|description| unescape(&description.value)).filter(|notes| !notes.trim().is_empty());
+    if let Some(due) = get("DUE") {
+        match parse_due(due) {
+            Some(date) => task.due = Some(date),
+            None => warn(format!("could not understand DUE \"{}\"", due.value)),
+        }
+    }
+    task.priority = get("PRIORITY").and_then(|priority| parse_priority(&priority.value));
+    task.completed_at = get("COMPLETED").and_then(parse_datetime);
+    match get("STATUS").map(|status| status.value.trim().to_uppercase()).as_deref() {
+        Some("COMPLETED") if task.completed_at.is_none() => task.completed_at = task.updated_at.or(Some(task.created_at)),
+        Some("CANCELLED") => {
+            warn(format!("\"{}\" was cancelled, imported as done", task.description));
+            task.completed_at = task.completed_at.or(task.updated_at).or(Some(task.created_at));
+        },
+        _ => {},
+    }
+    for categories in properties.iter().filter(|property| property.name == "CATEGORIES") {
+        // commas split categories unless they are escaped
+        for category in categories.value.replace("\\,", "\u{0}").split(',').map(|category| unescape(&category.replace('\u{0}', "\\,"))) {
+            if !category.trim().is_empty() {
+                add_tag(&mut task, &category, warn);
+            }
+        }
+    }
+    if let Some(rule) = get("RRULE") {
+        match parse_rrule(&rule.value) {
+            Some(recur) => task.recur = Some(recur),
+            None => warn(format!("RRULE \"{}\" has no taskz equivalent, dropped", rule.value)),
+        }
+    }
+    for related in properties.iter().filter(|property| property.name == "RELATED-TO") {
+        let Some(id) = id_for(related.value.trim()) else {
+            warn(format!("\"{}\" is related to {}, which is not in the file", task.description, related.value.trim()));
+            continue;
+        };
+        match related.param("RELTYPE").map(str::to_uppercase).as_deref() {
+            None | Some("PARENT") => task.parent = Some(id),
+            Some("DEPENDS-ON") => task.depends_on.push(id),
+            _ => {},
+        }
+    }
+    for comment in properties.iter().filter(|property| property.name == "COMMENT") {
+        let text = unescape(&comment.value);
+        let stamped = text.split_once(' ').filter(|(at, _)| at.contains('-')).and_then(|(at, text)| Some((parse_timestamp(at)?, text.to_string())));
+        task.annotations.push(match stamped {
+            Some((at, text)) => Annotation { at, text },
+            None => Annotation { at: task.created_at, text },
+        });
+    }
+    if let Some(project) = get("X-TASKZ-PROJECT") {
+        match parse_project(&unescape(&project.value).replace(' ', "-")) {
+            Some(project) => task.project = Some(project),
+            None => warn(format!("invalid project name \"{}\"", project.value)),
+        }
+    }
+    Some(task)
+}
+
+// the VTODOs in a calendar; events, journals and the like are counted and skipped
+pub fn read(text: &str) -> Result<Parsed, String> {
+    let lines = unfold(text);
+    if !lines.iter().any(|(_, line)| line.trim().eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err("no BEGIN:VCALENDAR found, this does not look like an ics file".to_string());
+    }
+    // components as (line where they start, properties)
+    let mut todos: Vec<(usize, Vec<Property>)> = Vec::new();
+    let mut current: Option<(usize, Vec<Property>)> = None;
+    let mut depth = 0;
+    let mut skipped = 0;
+    for (number, line) in &lines {
+        let Some(property) = parse_property(line.trim_end()) else {
+            continue;
+        };
+        match (property.name.as_str(), property.value.trim().to_uppercase().as_str()) {
+            ("BEGIN", "VTODO") if current.is_none() => current = Some((*number, Vec::new())),
+            ("END", "VTODO") if depth == 0 => todos.extend(current.take()),
+            // alarms and other components nested in a todo are not its properties
+            ("BEGIN", _) if current.is_some() => depth += 1,
+            ("END", _) if current.is_some() => depth -= 1,
+            ("BEGIN", "VEVENT" | "VJOURNAL") => skipped += 1,
+            _ if depth == 0 => {
+                if let Some((_, properties)) = current.as_mut() {
+                    properties.push(property);
+                }
+            },
+            _ => {},
+        }
+    }
+    let mut warnings = Vec::new();
+    if skipped > 0 {
+        warnings.push(format!("skipped {} event(s) or journal entries, only VTODOs are imported", skipped));
+    }
+    let ids: Vec<String> = todos.iter().map(|(_, properties)| {
+        properties.iter().find(|property| property.name == "UID").map(|uid| uid.value.trim().to_string()).unwrap_or_default()
+    }).collect();
+    let mut tasks = Vec::new();
+    for (line, properties) in &todos {
+        let mut warn = |message: String| warnings.push(format!("VTODO at line {}: {}", line, message));
+        tasks.extend(parse_todo(properties, &ids, &mut warn));
+    }
+    let skipped = skipped + todos.len() - tasks.len();
+    Ok(Parsed { tasks, warnings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{assert_same, sample};
+
+    #[test]
+    fn round_trip_keeps_every_field() {
+        let mut tasks = sample();
+        // long enough to be folded, with characters that need escaping
+        tasks[0].notes = Some("ask for the blue carton, semi-skimmed; two of them\nand a loaf — sliced if they have it — from the bakery counter".to_string());
+        let text = write(&tasks);
+        assert!(text.lines().all(|line| line.len() <= 76));
+        let parsed = read(&text).unwrap();
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_same(&parsed.tasks, &tasks);
+    }
+
+    #[test]
+    fn todos_from_other_apps() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:meeting\r\nEND:VEVENT\r\nBEGIN:VTODO\r\nUID:abc@example.com\r\nSUMMARY:file taxes\r\nPRIORITY:3\r\nSTATUS:CANCELLED\r\nCATEGORIES:Money Matters,Home\r\nRRULE:FREQ=YEARLY;COUNT=3\r\nBEGIN:VALARM\r\nSUMMARY:not the todo\r\nEND:VALARM\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let parsed = read(text).unwrap();
+        assert_eq!(parsed.skipped, 1);
+        let task = &parsed.tasks[0];
+        assert_eq!(task.description, "file taxes");
+        assert_eq!(task.priority, Some(Priority::High));
+        assert!(task.completed_at.is_some());
+        assert_eq!(task.tags, ["money-matters", "home"]);
+        assert_eq!(task.recur, None);
+        assert_eq!(parsed.warnings.len(), 3, "{:?}", parsed.warnings);
+    }
+
+    #[test]
+    fn rules_taskz_cannot_hold_are_dropped() {
+        assert_eq!(parse_rrule("FREQ=YEARLY;INTERVAL=2"), Some(Recurrence::Months(24)));
+        assert_eq!(parse_rrule("FREQ=YEARLY;INTERVAL=999999999"), None);
+        assert_eq!(parse_rrule("FREQ=WEEKLY;BYDAY=MO"), None);
+        assert_eq!(parse_rrule("FREQ=DAILY;UNTIL=20261231"), None);
+        assert!(read("BEGIN:VTODO\nEND:VTODO\n").is_err());
+    }
+}
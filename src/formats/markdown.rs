@@ -0,0 +1,183 @@
+use crate::{Task, extract_attributes};
+use super::Parsed;
+
+// headings `export --format md --group` sorts the checklist under
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Group {
+    Project,
+    Tag,
+}
+
+impl Group {
+    pub fn parse(name: &str) -> Option<Group> {
+        match name.to_lowercase().as_str() {
+            "project" | "projects" => Some(Group::Project),
+            "tag" | "tags" => Some(Group::Tag),
+            _ => None,
+        }
+    }
+}
+
+// a `- [ ] text <!-- #12 -->` line
+pub struct Item {
+    pub line: usize,
+    // the indentation and bullet, kept when the line is rewritten
+    pub prefix: String,
+    pub done: bool,
+    pub text: String,
+    pub id: Option<u32>,
+}
+
+// the description followed by the same inline attributes `add` takes
+pub fn item_text(task: &Task) -> String {
+    let mut parts = vec![task.description.clone()];
+    parts.extend(task.priority.map(|priority| format!("pri:{}", priority.letter())));
+    parts.extend(task.due.map(|due| format!("due:{}", due)));
+    parts.extend(task.recur.map(|recur| format!("recur:{}", recur.to_string().replace(' ', "-"))));
+    parts.extend(task.project.iter().map(|project| format!("project:{}", project)));
+    parts.extend(task.tags.iter().map(|tag| format!("+{}", tag)));
+    parts.join(" ")
+}
+
+pub fn item_line(prefix: &str, task: &Task) -> String {
+    let check = if task.completed_at.is_some() { 'x' } else { ' ' };
+    let id = if task.id != 0 { format!(" <!-- #{} -->", task.id) } else { String::new() };
+    format!("{}[{}] {}{}", prefix, check, item_text(task), id)
+}
+
+pub fn write(tasks: &[Task], group: Option<Group>) -> String {
+    let heading = |task: &Task| match group {
+        Some(Group::Project) => Some(task.project.clone().unwrap_or_else(|| "no project".to_string())),
+        // a task goes under its first tag only, so it is listed once
+        Some(Group::Tag) => Some(task.tags.first().cloned().unwrap_or_else(|| "untagged".to_string())),
+        None => None,
+    };
+    let mut headings: Vec<Option<String>> = Vec::new();
+    for task in tasks {
+        let name = heading(task);
+        if !headings.contains(&name) {
+            headings.push(name);
+        }
+    }
+    let mut sections = Vec::new();
+    for name in headings {
+        let mut section = String::new();
+        if let Some(name) = &name {
+            section.push_str(&format!("## {}\n\n", name));
+        }
+        for task in tasks.iter().filter(|task| heading(task) == name) {
+            section.push_str(&item_line("- ", task));
+            section.push('\n');
+        }
+        sections.push(section);
+    }
+    sections.join("\n")
+}
+
+fn parse_item(line: &str) -> Option<(String, bool, &str)> {
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+    let bullet = ["- ", "* ", "+ "].iter().find(|bullet| rest.starts_with(**bullet))?;
+    let rest = &rest[bullet.len()..];
+    let done = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((format!("{}{}", indent, bullet), done, rest[3..].trim()))
+}
+
+// `<!-- #12 -->` at the end of the item links it to a task
+fn split_id(text: &str) -> (&str, Option<u32>) {
+    let id = text.strip_suffix("-->")
+        .and_then(|rest| rest.rsplit_once("<!--"))
+        .and_then(|(text, comment)| Some((text.trim_end(), comment.trim().strip_prefix('#')?.parse().ok()?)));
+    match id {
+        Some((text, id)) => (text, Some(id)),
+        None => (text, None),
+    }
+}
+
+// the checklist items in a markdown file; other lines are left alone
+pub fn items(text: &str) -> Vec<Item> {
+    text.lines().enumerate().filter_map(|(line, content)| {
+        let (prefix, done, rest) = parse_item(content)?;
+        let (text, id) = split_id(rest);
+        (!text.is_empty()).then(|| Item { line, prefix, done, text: text.to_string(), id })
+    }).collect()
+}
+
+// a new task from an item's text, with any inline attributes applied
+pub fn task_for(item: &Item, warn: &mut dyn FnMut(String)) -> Task {
+    let words: Vec<String> = item.text.split_whitespace().map(str::to_string).collect();
+    let mut task = Task::new(0, item.text.clone());
+    match extract_attributes(&words) {
+        Ok((description, attributes)) if !description.is_empty() => {
+            task.description = description;
+            task.due = attributes.due;
+            task.priority = attributes.priority;
+            task.tags = attributes.tags;
+            task.project = attributes.project;
+            task.recur = attributes.recur;
+        },
+        Ok(_) => {},
+        Err(e) => warn(format!("{}, kept as plain text", e)),
+    }
+    if item.done {
+        task.completed_at = Some(task.created_at);
+    }
+    task
+}
+
+pub fn read(text: &str) -> Parsed {
+    let mut warnings = Vec::new();
+    let tasks = items(text).iter().map(|item| {
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", item.line + 1, message));
+        let mut task = task_for(item, &mut warn);
+        task.id = item.id.unwrap_or(0);
+        task
+    }).collect();
+    Parsed { tasks, warnings, skipped: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::sample;
+
+    #[test]
+    fn round_trip_keeps_the_inline_attributes() {
+        let tasks = sample();
+        let parsed = read(&write(&tasks, None));
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(parsed.tasks.len(), tasks.len());
+        for (read, written) in parsed.tasks.iter().zip(&tasks) {
+            assert_eq!(read.id, written.id);
+            assert_eq!(read.description, written.description);
+            assert_eq!(read.completed_at.is_some(), written.completed_at.is_some());
+            assert_eq!(read.due, written.due);
+            assert_eq!(read.priority, written.priority);
+            assert_eq!(read.tags, written.tags);
+            assert_eq!(read.project, written.project);
+            assert_eq!(read.recur, written.recur);
+        }
+    }
+
+    #[test]
+    fn groups_become_headings() {
+        let text = write(&sample(), Some(Group::Project));
+        assert!(text.starts_with("## house\n\n- [ ] buy milk"));
+        assert!(text.contains("\n## no project\n\n- [x] find the list pri:L <!-- #2 -->\n- [ ] cook dinner"));
+        assert_eq!(read(&text).tasks.len(), 3);
+    }
+
+    #[test]
+    fn only_checklist_items_are_read() {
+        let text = "# notes\n\nsome prose\n- plain bullet\n  * [X] nested done <!-- #4 -->\n+ [ ] new one\n- [?] not a box\n- [ ]   \n";
+        let items = items(text);
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].line, items[0].prefix.as_str(), items[0].done, items[0].id), (4, "  * ", true, Some(4)));
+        assert_eq!(items[0].text, "nested done");
+        assert_eq!((items[1].done, items[1].id, items[1].text.as_str()), (false, None, "new one"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
+use crate::{Task, dates, parse_tag, today};
+
+mod csv;
+mod ics;
+pub mod markdown;
+mod taskwarrior;
+mod todoist;
+pub mod todotxt;
+mod trello;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    TodoTxt,
+    Markdown,
+    Ics,
+    // import only, from other tools' exports
+    Taskwarrior,
+    TodoistCsv,
+    TrelloJson,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "todo.txt" | "todotxt" | "txt" => Some(Format::TodoTxt),
+            "md" | "markdown" => Some(Format::Markdown),
+            "ics" | "ical" | "icalendar" => Some(Format::Ics),
+            "taskwarrior" | "tw" => Some(Format::Taskwarrior),
+            "todoist-csv" | "todoist" => Some(Format::TodoistCsv),
+            "trello-json" | "trello" => Some(Format::TrelloJson),
+            _ => None,
+        }
+    }
+
+    // guessed from the file extension when no --format is given
+    pub fn from_path(path: &str) -> Option<Format> {
+        let extension = path.rsplit_once('.')?.1;
+        Format::parse(extension)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::TodoTxt => "todo.txt",
+            Format::Markdown => "md",
+            Format::Ics => "ics",
+            Format::Taskwarrior => "taskwarrior",
+            Format::TodoistCsv => "todoist-csv",
+            Format::TrelloJson => "trello-json",
+        }
+    }
+
+    pub fn can_export(self) -> bool {
+        !matches!(self, Format::Taskwarrior | Format::TodoistCsv | Format::TrelloJson)
+    }
+}
+
+// tasks read from a file, with ids as the file had them, plus anything that
+// could not be carried over
+pub struct Parsed {
+    pub tasks: Vec<Task>,
+    pub warnings: Vec<String>,
+    // entries in the file that did not become tasks at all
+    pub skipped: usize,
+}
+
+pub fn write(format: Format, tasks: &[Task]) -> Result<String, String> {
+    match format {
+        Format::Csv => self::csv::write(tasks),
+        Format::TodoTxt => Ok(todotxt::write(tasks)),
+        Format::Markdown => Ok(markdown::write(tasks, None)),
+        Format::Ics => Ok(ics::write(tasks)),
+        Format::Taskwarrior | Format::TodoistCsv | Format::TrelloJson => Err(format!("{} can only be imported", format.name())),
+    }
+}
+
+pub fn read(format: Format, text: &str) -> Result<Parsed, String> {
+    match format {
+        Format::Csv => self::csv::read(text),
+        Format::TodoTxt => Ok(todotxt::read(text)),
+        Format::Markdown => Ok(markdown::read(text)),
+        Format::Ics => ics::read(text),
+        Format::Taskwarrior => taskwarrior::read(text),
+        Format::TodoistCsv => todoist::read(text),
+        Format::TrelloJson => trello::read(text),
+    }
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true)).unwrap_or_default()
+}
+
+// RFC 3339, or "2026-10-18 14:30[:00]" and plain dates in local time
+fn parse_datetime(value: &str) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local));
+    }
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))?;
+    Local.from_local_datetime(&local).earliest()
+}
+
+// any of the above, or unix seconds
+pub fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    value.parse::<i64>().ok().or_else(|| parse_datetime(value).map(|time| time.timestamp()))
+}
+
+// a tag or project name from free text, "Home Garden" becomes "home-garden"
+pub fn slug(name: &str) -> String {
+    let words: Vec<&str> = name.split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_').filter(|word| !word.is_empty()).collect();
+    words.join("-").to_lowercase()
+}
+
+// the local calendar day of a timestamp
+pub fn local_date(timestamp: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.with_timezone(&Local).date_naive())
+}
+
+// a tag from another tool's label, category or list name
+pub fn tag(name: &str) -> Option<String> {
+    parse_tag(&format!("+{}", slug(name)))
+}
+
+pub fn add_tag(task: &mut Task, name: &str, warn: &mut dyn FnMut(String)) {
+    match tag(name) {
+        Some(tag) => {
+            task.add_tag(&tag);
+        },
+        None => warn(format!("invalid tag \"{}\"", name)),
+    }
+}
+
+// one warning per kind of data left behind, rather than one per task
+pub fn warn_dropped(dropped: &BTreeMap<String, usize>, noun: &str, warnings: &mut Vec<String>) {
+    for (what, count) in dropped {
+        warnings.push(format!("dropped {} from {} {}(s), taskz has no equivalent", what, count, noun));
+    }
+}
+
+// exact dates and times first, then anything `due:` understands
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    parse_datetime(value).map(|time| time.date_naive()).or_else(|| dates::parse_date(value, today()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use chrono::{Local, NaiveDate, TimeZone};
+    use crate::{Annotation, Priority, Task, dates::Recurrence};
+
+    // midnight in local time, which survives formats that only keep dates
+    pub fn day(day: u32) -> i64 {
+        Local.with_ymd_and_hms(2026, 10, day, 0, 0, 0).earliest().unwrap().timestamp()
+    }
+
+    // a task with every field set, a completed subtask and a dependent task
+    pub fn sample() -> Vec<Task> {
+        let mut full = Task::new(1, "buy milk, eggs & \"good\" bread; then cook".to_string());
+        full.created_at = day(1);
+        full.updated_at = Some(day(2));
+        full.due = NaiveDate::from_ymd_opt(2026, 10, 20);
+        full.priority = Some(Priority::High);
+        full.tags = vec!["errand".to_string(), "home-made".to_string()];
+        full.project = Some("house".to_string());
+        full.recur = Some(Recurrence::Weeks(2));
+        full.notes = Some("semi-skimmed, the blue carton".to_string());
+        full.annotations = vec![Annotation { at: day(2), text: "shop closes at 6".to_string() }];
+        let mut done = Task::new(2, "find the list".to_string());
+        done.created_at = day(1);
+        done.completed_at = Some(day(3));
+        done.parent = Some(1);
+        done.priority = Some(Priority::Low);
+        let mut waiting = Task::new(3, "cook dinner".to_string());
+        waiting.created_at = day(4);
+        waiting.depends_on = vec![1, 2];
+        waiting.recur = Some(Recurrence::MonthDay(31));
+        vec![full, done, waiting]
+    }
+
+    // tasks compared the way they are stored
+    pub fn assert_same(read: &[Task], expected: &[Task]) {
+        assert_eq!(serde_json::to_value(read).unwrap(), serde_json::to_value(expected).unwrap());
+    }
+
+    #[test]
+    fn slugs_are_valid_tag_names() {
+        assert_eq!(super::slug("Home  Garden"), "home-garden");
+        assert_eq!(super::slug("Errands & Shopping"), "errands-shopping");
+        assert_eq!(super::tag("Waiting on: Bob!"), Some("waiting-on-bob".to_string()));
+        assert_eq!(super::tag("!!!"), None);
+    }
+
+    #[test]
+    fn timestamps_read_back() {
+        assert_eq!(super::parse_timestamp(&super::format_timestamp(day(5))), Some(day(5)));
+        assert_eq!(super::parse_timestamp("2026-10-05"), Some(day(5)));
+        assert_eq!(super::parse_timestamp(&day(5).to_string()), Some(day(5)));
+        assert_eq!(super::parse_timestamp("soon"), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use crate::{Annotation, Priority, Task, dates::Recurrence, parse_project};
+use super::{Parsed, add_tag, local_date, parse_timestamp, warn_dropped};
+
+// fields that are bookkeeping in taskwarrior and mean nothing once imported
+const IGNORED: [&str; 9] = ["id", "uuid", "urgency", "imask", "mask", "parent", "rtype", "last", "status"];
+
+// `20261018T120000Z`, as `task export` writes them
+fn parse_date(value: &Value) -> Option<i64> {
+    let value = value.as_str()?;
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()
+        .map(|time| time.and_utc().timestamp())
+        .or_else(|| parse_timestamp(value))
+}
+
+// taskwarrior's duration names that taskz's own parser doesn't know
+fn parse_recurrence(value: &str) -> Option<Recurrence> {
+    match value.trim().to_lowercase().as_str() {
+        "biweekly" | "fortnight" => Some(Recurrence::Weeks(2)),
+        "quarterly" => Some(Recurrence::Months(3)),
+        "semiannual" => Some(Recurrence::Months(6)),
+        "annual" | "yearly" => Some(Recurrence::Months(12)),
+        "weekdays" => Some(Recurrence::Weekdays),
+        other => Recurrence::parse(other),
+    }
+}
+
+// `depends` is an array in newer versions and a comma-separated string in older ones
+fn uuids(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(|item| item.as_str().map(str::to_string)).collect(),
+        Value::String(list) => list.split(',').map(|uuid| uuid.trim().to_string()).filter(|uuid| !uuid.is_empty()).collect(),
+        _ => vec![],
+    }
+}
+
+// a JSON array, or one object per line as older versions exported
+fn objects(text: &str) -> Result<Vec<Value>, String> {
+    if let Ok(Value::Array(items)) = serde_json::from_str(text) {
+        return Ok(items);
+    }
+    text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).map(|(i, line)| {
+        serde_json::from_str(line.trim().trim_end_matches(',')).map_err(|e| format!("line {}: not a taskwarrior export: {}", i + 1, e))
+    }).collect()
+}
+
+pub fn read(text: &str) -> Result<Parsed, String> {
+    let objects = objects(text)?;
+    let mut warnings = Vec::new();
+    let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut skipped = 0;
+    // deleted tasks and recurrence templates are left out, everything else keeps its place for ids
+    let status = |object: &Value| object.get("status").and_then(Value::as_str).unwrap_or("pending").to_string();
+    let kept: Vec<&Value> = objects.iter().filter(|object| {
+        let status = status(object);
+        let description = object.get("description").and_then(Value::as_str).unwrap_or_default();
+        match status.as_str() {
+            "deleted" => warnings.push(format!("skipped deleted task \"{}\"", description)),
+            "recurring" => warnings.push(format!("skipped the recurrence template of \"{}\", its pending instances are imported", description)),
+            _ if description.trim().is_empty() => warnings.push("skipped a task without a description".to_string()),
+            _ => return true,
+        }
+        skipped += 1;
+        false
+    }).collect();
+    let ids: Vec<&str> = kept.iter().map(|object| object.get("uuid").and_then(Value::as_str).unwrap_or_default()).collect();
+    let id_for = |uuid: &str| ids.iter().position(|other| !uuid.is_empty() && *other == uuid).map(|i| i as u32 + 1);
+    let mut tasks = Vec::new();
+    for (i, object) in kept.iter().enumerate() {
+        let Some(fields) = object.as_object() else {
+            continue;
+        };
+        let description = fields.get("description").and_then(Value::as_str).unwrap_or_default().trim().to_string();
+        let mut warn = |message: String| warnings.push(format!("\"{}\": {}", description, message));
+        let mut task = Task::new(i as u32 + 1, description.clone());
+        for (key, value) in fields {
+            match key.as_str() {
+                "description" => {},
+                "entry" => task.created_at = parse_date(value).unwrap_or(task.created_at),
+                "modified" => task.updated_at = parse_date(value),
+                "end" if status(object) == "completed" => task.completed_at = parse_date(value),
+                "end" => {},
+                "due" => task.due = parse_date(value).and_then(local_date),
+                "priority" => match value.as_str().and_then(Priority::parse) {
+                    Some(priority) => task.priority = Some(priority),
+                    None => warn(format!("unknown priority {}", value)),
+                },
+                "tags" => {
+                    let tags = match value {
+                        Value::Array(tags) => tags.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+                        Value::String(tags) => tags.split(',').map(str::to_string).collect(),
+                        _ => vec![],
+                    };
+                    for tag in tags {
+                        add_tag(&mut task, &tag, &mut warn);
+                    }
+                },
+                "project" => match value.as_str().and_then(|project| parse_project(&project.replace(' ', "-"))) {
+                    Some(project) => task.project = Some(project),
+                    None => warn(format!("invalid project name {}", value)),
+                },
+                "recur" => match value.as_str().and_then(parse_recurrence) {
+                    Some(recur) => task.recur = Some(recur),
+                    None => warn(format!("recurrence {} has no taskz equivalent, dropped", value)),
+                },
+                "depends" => {
+                    for uuid in uuids(value) {
+                        match id_for(&uuid) {
+                            Some(id) => task.depends_on.push(id),
+                            None => warn(format!("depends on {}, which is not in the file", uuid)),
+                        }
+                    }
+                },
+                "annotations" => {
+                    for annotation in value.as_array().into_iter().flatten() {
+                        let text = annotation.get("description").and_then(Value::as_str).unwrap_or_default().to_string();
+                        let at = annotation.get("entry").and_then(parse_date).unwrap_or(task.created_at);
+                        task.annotations.push(Annotation { at, text });
+                    }
+                },
+                key if IGNORED.contains(&key) => {},
+                // wait, scheduled, until, start and any UDAs
+                key => *dropped.entry(key.to_string()).or_default() += 1,
+            }
+        }
+        if status(object) == "completed" && task.completed_at.is_none() {
+            task.completed_at = task.updated_at.or(Some(task.created_at));
+        }
+        tasks.push(task);
+    }
+    warn_dropped(&dropped, "task", &mut warnings);
+    Ok(Parsed { tasks, warnings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+{"id":1,"uuid":"aaa","description":"write report","status":"pending","entry":"20261001T080000Z","due":"20261020T120000Z","priority":"H","project":"Work.Web","tags":["Client A","urgent"],"recur":"biweekly","wait":"20261005T000000Z","annotations":[{"entry":"20261002T090000Z","description":"draft sent"}]},
+{"id":0,"uuid":"bbb","description":"old idea","status":"deleted"},
+{"id":0,"uuid":"ccc","description":"send invoice","status":"completed","entry":"20261001T080000Z","end":"20261003T100000Z","depends":"aaa,zzz"}
+]"#;
+
+    #[test]
+    fn reads_a_task_export() {
+        let parsed = read(EXPORT).unwrap();
+        assert_eq!(parsed.skipped, 1);
+        let [report, invoice] = &parsed.tasks[..] else {
+            panic!("expected two tasks, got {}", parsed.tasks.len());
+        };
+        assert_eq!((report.id, report.description.as_str()), (1, "write report"));
+        assert_eq!(report.created_at, 1_790_841_600);
+        assert_eq!(report.priority, Some(Priority::High));
+        assert_eq!(report.project.as_deref(), Some("work.web"));
+        assert_eq!(report.tags, ["client-a", "urgent"]);
+        assert_eq!(report.recur, Some(Recurrence::Weeks(2)));
+        assert_eq!(report.annotations[0].text, "draft sent");
+        assert!(report.due.is_some());
+        assert_eq!(invoice.completed_at, parse_date(&Value::from("20261003T100000Z")));
+        assert_eq!(invoice.depends_on, [1]);
+        assert!(parsed.warnings.iter().any(|warning| warning.contains("depends on zzz")));
+        assert!(parsed.warnings.iter().any(|warning| warning == "dropped wait from 1 task(s), taskz has no equivalent"));
+    }
+
+    #[test]
+    fn reads_one_object_per_line() {
+        let parsed = read("{\"description\":\"a\",\"status\":\"pending\"},\n{\"description\":\"b\",\"status\":\"recurring\"}\n").unwrap();
+        assert_eq!(parsed.tasks.len(), 1);
+        assert_eq!(parsed.skipped, 1);
+        assert!(read("not json").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use ::csv::ReaderBuilder;
+use crate::{Annotation, Priority, Task, dates::Recurrence, parse_tag};
+use super::{Parsed, parse_date, tag, warn_dropped};
+
+// the CSV priority runs from 1 (p1, the highest) to 4 (no priority)
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.trim() {
+        "1" => Some(Priority::High),
+        "2" => Some(Priority::Medium),
+        "3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+// "every week", "every 2 weeks", "every weekday"...; todoist's richer
+// schedules ("every other monday") have no taskz equivalent
+fn parse_recurrence(value: &str) -> Option<Recurrence> {
+    let value = value.trim().to_lowercase();
+    let rest = value.strip_prefix("every ")?;
+    match rest {
+        "day" => Some(Recurrence::Days(1)),
+        "week" => Some(Recurrence::Weeks(1)),
+        "month" => Some(Recurrence::Months(1)),
+        "year" => Some(Recurrence::Months(12)),
+        "weekday" | "workday" => Some(Recurrence::Weekdays),
+        _ => Recurrence::parse(&value).or_else(|| Recurrence::parse(rest)),
+    }
+}
+
+// labels are written into the content as `@label`
+fn split_labels(content: &str) -> (String, Vec<String>) {
+    let mut labels = Vec::new();
+    let mut words = Vec::new();
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@').and_then(|label| parse_tag(&format!("+{}", label))) {
+            Some(label) => labels.push(label),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), labels)
+}
+
+// the "export as template" csv: one row per task, section or comment, in order
+pub fn read(text: &str) -> Result<Parsed, String> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let headers: Vec<String> = reader.headers().map_err(|e| format!("could not read the csv header: {}", e))?
+        .iter().map(|header| header.trim().to_uppercase()).collect();
+    if !headers.iter().any(|header| header == "TYPE") || !headers.iter().any(|header| header == "CONTENT") {
+        return Err("this does not look like a todoist export, it needs TYPE and CONTENT columns".to_string());
+    }
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut skipped = 0;
+    let mut section: Option<String> = None;
+    // ids of the tasks above the current row, by indent level
+    let mut parents: Vec<u32> = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let line = row + 2;
+        let record = record.map_err(|e| format!("line {}: {}", line, e))?;
+        let value = |name: &str| headers.iter().position(|header| header == name).and_then(|i| record.get(i)).map(str::trim).filter(|value| !value.is_empty());
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", line, message));
+        let content = value("CONTENT").unwrap_or_default();
+        match value("TYPE").map(str::to_lowercase).as_deref() {
+            Some("section") => {
+                section = tag(content);
+                parents.clear();
+            },
+            Some("note") => match tasks.last_mut() {
+                Some(task) => task.annotations.push(Annotation { at: task.created_at, text: content.to_string() }),
+                None => {
+                    warn("comment before any task, skipped".to_string());
+                    skipped += 1;
+                },
+            },
+            Some("task") => {
+                let (description, labels) = split_labels(content);
+                if description.is_empty() {
+                    warn("no content, skipped".to_string());
+                    skipped += 1;
+                    continue;
+                }
+                let mut task = Task::new(tasks.len() as u32 + 1, description);
+                for label in labels.iter().chain(section.iter()) {
+                    task.add_tag(label);
+                }
+                task.notes = value("DESCRIPTION").map(str::to_string);
+                task.priority = value("PRIORITY").and_then(parse_priority);
+                let indent = value("INDENT").and_then(|indent| indent.parse::<usize>().ok()).unwrap_or(1).max(1);
+                parents.truncate(indent - 1);
+                task.parent = parents.last().copied().filter(|_| parents.len() == indent - 1);
+                parents.push(task.id);
+                match (value("DATE"), value("DEADLINE")) {
+                    (Some(date), deadline) => {
+                        if let Some(recur) = parse_recurrence(date) {
+                            task.recur = Some(recur);
+                        } else if date.to_lowercase().starts_with("every") {
+                            warn(format!("recurring date \"{}\" has no taskz equivalent, dropped", date));
+                        } else {
+                            match parse_date(date) {
+                                Some(due) => task.due = Some(due),
+                                None => warn(format!("could not understand the date \"{}\"", date)),
+                            }
+                        }
+                        if deadline.is_some() {
+                            *dropped.entry("a deadline next to a date".to_string()).or_default() += 1;
+                        }
+                    },
+                    (None, Some(deadline)) => task.due = parse_date(deadline),
+                    (None, None) => {},
+                }
+                if value("RESPONSIBLE").is_some() {
+                    *dropped.entry("the assignee".to_string()).or_default() += 1;
+                }
+                if value("DURATION").is_some() {
+                    *dropped.entry("the duration".to_string()).or_default() += 1;
+                }
+                tasks.push(task);
+            },
+            // "meta" rows hold view settings, blank rows separate sections
+            _ => {},
+        }
+    }
+    warn_dropped(&dropped, "task", &mut warnings);
+    Ok(Parsed { tasks, warnings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE,DURATION,DURATION_UNIT,DEADLINE
+meta,view_style=list,,,,,,,,,,,
+section,Errands & Shopping,,,,,,,,,,,
+task,buy milk @home @urgent,two percent,1,1,Sam,,2026-10-20,en,,,,
+note,they close at 6,,,,,,,,,,,
+task,compare prices,,4,2,Sam,Alex,every 2 weeks,en,,30,minute,
+task,renew passport,,3,1,Sam,,every other monday,en,,,,
+";
+
+    #[test]
+    fn reads_a_template_export() {
+        let parsed = read(EXPORT).unwrap();
+        let [milk, prices, passport] = &parsed.tasks[..] else {
+            panic!("expected three tasks, got {}", parsed.tasks.len());
+        };
+        assert_eq!(milk.description, "buy milk");
+        assert_eq!(milk.tags, ["home", "urgent", "errands-shopping"]);
+        assert_eq!(milk.priority, Some(Priority::High));
+        assert_eq!(milk.notes.as_deref(), Some("two percent"));
+        assert_eq!(milk.due, chrono::NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(milk.annotations[0].text, "they close at 6");
+        assert_eq!(prices.parent, Some(milk.id));
+        assert_eq!(prices.priority, None);
+        assert_eq!(prices.recur, Some(Recurrence::Weeks(2)));
+        assert_eq!((passport.parent, passport.recur, passport.priority), (None, None, Some(Priority::Low)));
+        assert_eq!(parsed.warnings, [
+            "line 7: recurring date \"every other monday\" has no taskz equivalent, dropped",
+            "dropped the assignee from 1 task(s), taskz has no equivalent",
+            "dropped the duration from 1 task(s), taskz has no equivalent",
+        ]);
+    }
+
+    #[test]
+    fn other_csv_files_are_refused() {
+        assert!(read("id,description\n1,a\n").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{Local, NaiveDate, TimeZone};
+use crate::{Priority, Task, dates::Recurrence, parse_project, parse_tag};
+use super::{Parsed, local_date};
+
+// todo.txt only has letters; A-C map onto taskz's three levels
+fn letter_for(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+fn priority_for(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+// the `rec:` extension: "1d", "2w", "1m", "1y" and "1b" for business days
+fn write_recurrence(recur: Recurrence) -> String {
+    match recur {
+        Recurrence::Days(n) => format!("{}d", n),
+        Recurrence::Weeks(n) => format!("{}w", n),
+        Recurrence::Months(n) if n % 12 == 0 => format!("{}y", n / 12),
+        Recurrence::Months(n) => format!("{}m", n),
+        // no todo.txt equivalent, kept readable so taskz can read it back
+        Recurrence::MonthDay(day) => format!("monthly-on-day-{}", day),
+        Recurrence::Weekdays => "1b".to_string(),
+    }
+}
+
+fn parse_recurrence(value: &str) -> Option<Recurrence> {
+    // a leading + means "from the due date" in todo.txt, which is what taskz always does
+    let value = value.trim_start_matches('+');
+    if value.ends_with('b') {
+        return Some(Recurrence::Weekdays);
+    }
+    Recurrence::parse(value)
+}
+
+fn start_of(date: NaiveDate) -> Option<i64> {
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest().map(|time| time.timestamp())
+}
+
+fn join_ids(ids: &[u32]) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",")
+}
+
+// `x 2026-10-18 2026-10-01 write report +ops @work due:2026-10-23 id:3`
+pub fn write_line(task: &Task) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    match (task.completed_at, task.priority) {
+        (Some(completed_at), _) => {
+            parts.push("x".to_string());
+            parts.extend(local_date(completed_at).map(|date| date.to_string()));
+        },
+        (None, Some(priority)) => parts.push(format!("({})", letter_for(priority))),
+        (None, None) => {},
+    }
+    parts.extend(local_date(task.created_at).map(|date| date.to_string()));
+    parts.push(task.description.clone());
+    parts.extend(task.project.iter().map(|project| format!("+{}", project)));
+    parts.extend(task.tags.iter().map(|tag| format!("@{}", tag)));
+    parts.extend(task.due.map(|due| format!("due:{}", due)));
+    parts.extend(task.recur.map(|recur| format!("rec:{}", write_recurrence(recur))));
+    // completed lines lose their (A), so the priority moves into an extension
+    if let (Some(_), Some(priority)) = (task.completed_at, task.priority) {
+        parts.push(format!("pri:{}", letter_for(priority)));
+    }
+    parts.extend(task.parent.map(|parent| format!("parent:{}", parent)));
+    if !task.depends_on.is_empty() {
+        parts.push(format!("dep:{}", join_ids(&task.depends_on)));
+    }
+    if task.id != 0 {
+        parts.push(format!("id:{}", task.id));
+    }
+    parts.join(" ")
+}
+
+pub fn write(tasks: &[Task]) -> String {
+    tasks.iter().map(|task| format!("{}\n", write_line(task))).collect()
+}
+
+fn parse_line(line: &str, warn: &mut dyn FnMut(String)) -> Task {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let date = |word: Option<&&str>| word.and_then(|word| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok());
+    let mut completed = None;
+    let mut priority = None;
+    if words.first() == Some(&"x") {
+        words.remove(0);
+        completed = Some(date(words.first()));
+        if completed.flatten().is_some() {
+            words.remove(0);
+        }
+    } else if let Some(letter) = words.first().and_then(|word| word.strip_prefix('(')?.strip_suffix(')')) {
+        let mut letters = letter.chars();
+        if let (Some(letter), None) = (letters.next(), letters.next()) {
+            priority = priority_for(letter);
+            if priority.is_some() {
+                words.remove(0);
+                if !matches!(letter, 'A'..='C') {
+                    warn(format!("priority ({}) imported as L", letter));
+                }
+            }
+        }
+    }
+    let created = date(words.first());
+    if created.is_some() {
+        words.remove(0);
+    }
+    let mut task = Task::new(0, String::new());
+    task.priority = priority;
+    if let Some(created) = created.and_then(start_of) {
+        task.created_at = created;
+    }
+    // a bare "x" still marks the task done, dated like its creation
+    task.completed_at = completed.map(|date| date.and_then(start_of).unwrap_or(task.created_at));
+    let mut description = Vec::new();
+    for word in words {
+        if let Some(name) = word.strip_prefix('+').filter(|name| !name.is_empty()) {
+            // taskz has one project per task, any further ones become tags
+            match (&task.project, parse_project(name)) {
+                (None, Some(project)) => task.project = Some(project),
+                _ => match parse_tag(&format!("+{}", name)) {
+                    Some(tag) => {
+                        task.add_tag(&tag);
+                    },
+                    None => description.push(word),
+                },
+            }
+            continue;
+        }
+        if let Some(tag) = word.strip_prefix('@').and_then(|name| parse_tag(&format!("+{}", name))) {
+            task.add_tag(&tag);
+            continue;
+        }
+        let Some((key, value)) = word.split_once(':').filter(|(_, value)| !value.is_empty()) else {
+            description.push(word);
+            continue;
+        };
+        match key {
+            "due" => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(due) => task.due = Some(due),
+                Err(_) => warn(format!("could not understand the due date \"{}\"", value)),
+            },
+            "rec" => match parse_recurrence(value) {
+                Some(recur) => task.recur = Some(recur),
+                None => warn(format!("unknown recurrence \"{}\"", value)),
+            },
+            "pri" => task.priority = value.chars().next().and_then(priority_for),
+            "id" => task.id = value.parse().unwrap_or(0),
+            "parent" => task.parent = value.parse().ok(),
+            "dep" => task.depends_on = value.split(',').filter_map(|id| id.parse().ok()).collect(),
+            _ => description.push(word),
+        }
+    }
+    task.description = description.join(" ");
+    task
+}
+
+// blank lines are skipped, everything else is a task
+pub fn read(text: &str) -> Parsed {
+    let mut tasks = Vec::new();
+    let mut warnings = Vec::new();
+    let mut skipped = 0;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", i + 1, message));
+        let task = parse_line(line, &mut warn);
+        if task.description.is_empty() {
+            warn("no description, skipped".to_string());
+            skipped += 1;
+            continue;
+        }
+        tasks.push(task);
+    }
+    Parsed { tasks, warnings, skipped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{assert_same, sample};
+
+    #[test]
+    fn round_trip_keeps_what_a_line_can_hold() {
+        let tasks = sample();
+        let parsed = read(&write(&tasks));
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        // notes, annotations and edit times have no place in todo.txt
+        let expected: Vec<Task> = tasks.into_iter().map(|mut task| {
+            task.updated_at = None;
+            task.notes = None;
+            task.annotations.clear();
+            task
+        }).collect();
+        assert_same(&parsed.tasks, &expected);
+    }
+
+    #[test]
+    fn lines_from_other_apps() {
+        let parsed = read("(D) 2026-10-01 call +Mom @phone +family due:2026-10-09 rec:+1b\nx fix bike\n\n");
+        assert_eq!(parsed.warnings, ["line 1: priority (D) imported as L"]);
+        let call = &parsed.tasks[0];
+        assert_eq!(call.description, "call");
+        assert_eq!(call.priority, Some(Priority::Low));
+        assert_eq!(call.project.as_deref(), Some("mom"));
+        assert_eq!(call.tags, ["phone", "family"]);
+        assert_eq!(call.due, NaiveDate::from_ymd_opt(2026, 10, 9));
+        assert_eq!(call.recur, Some(Recurrence::Weekdays));
+        assert_eq!(parsed.tasks[1].description, "fix bike");
+        assert_eq!(parsed.tasks[1].completed_at, Some(parsed.tasks[1].created_at));
+    }
+}
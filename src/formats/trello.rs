@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use serde_json::Value;
+use crate::{Annotation, Task, parse_project};
+use super::{Parsed, add_tag, local_date, parse_timestamp, slug, warn_dropped};
+
+// lists whose cards count as finished
+const DONE_LISTS: [&str; 4] = ["done", "complete", "completed", "finished"];
+
+fn field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default().trim()
+}
+
+fn timestamp(value: &Value, key: &str) -> Option<i64> {
+    value.get(key).and_then(Value::as_str).and_then(parse_timestamp)
+}
+
+// trello ids start with the creation time in hex
+fn created_from_id(id: &str) -> Option<i64> {
+    i64::from_str_radix(id.get(..8)?, 16).ok()
+}
+
+// a board's "export as JSON": cards become tasks, checklist items their
+// subtasks and comments their annotations
+pub fn read(text: &str) -> Result<Parsed, String> {
+    let board: Value = serde_json::from_str(text).map_err(|e| format!("not valid JSON: {}", e))?;
+    let Some(cards) = board.get("cards").and_then(Value::as_array) else {
+        return Err("this does not look like a trello board export, it has no cards".to_string());
+    };
+    let empty = Vec::new();
+    let array = |key: &str| board.get(key).and_then(Value::as_array).unwrap_or(&empty);
+    let lists = array("lists");
+    let checklists = array("checklists");
+    let actions = array("actions");
+    let project = parse_project(&slug(field(&board, "name")));
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut dropped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut skipped = 0;
+    for card in cards {
+        let name = field(card, "name");
+        if name.is_empty() {
+            warnings.push("skipped a card without a name".to_string());
+            skipped += 1;
+            continue;
+        }
+        let mut warn = |message: String| warnings.push(format!("card \"{}\": {}", name, message));
+        let card_id = field(card, "id");
+        let list = lists.iter().find(|list| field(list, "id") == field(card, "idList"));
+        let list_name = list.map(|list| field(list, "name")).unwrap_or_default();
+        let mut task = Task::new(tasks.len() as u32 + 1, name.to_string());
+        task.project = project.clone();
+        task.created_at = created_from_id(card_id).unwrap_or(task.created_at);
+        task.updated_at = timestamp(card, "dateLastActivity");
+        task.notes = Some(field(card, "desc").to_string()).filter(|notes| !notes.is_empty());
+        task.due = timestamp(card, "due").and_then(local_date);
+        if !list_name.is_empty() {
+            add_tag(&mut task, list_name, &mut warn);
+        }
+        for label in card.get("labels").and_then(Value::as_array).into_iter().flatten() {
+            // unnamed labels are only a color
+            let label = match field(label, "name") {
+                "" => field(label, "color"),
+                name => name,
+            };
+            if !label.is_empty() {
+                add_tag(&mut task, label, &mut warn);
+            }
+        }
+        // archived cards and lists are treated as finished work
+        let archived = card.get("closed").and_then(Value::as_bool).unwrap_or(false)
+            || list.and_then(|list| list.get("closed")).and_then(Value::as_bool).unwrap_or(false);
+        let done = card.get("dueComplete").and_then(Value::as_bool).unwrap_or(false)
+            || DONE_LISTS.contains(&list_name.to_lowercase().as_str());
+        if done || archived {
+            task.completed_at = task.updated_at.or(Some(task.created_at));
+        }
+        let mut comments: Vec<Annotation> = actions.iter()
+            .filter(|action| field(action, "type") == "commentCard")
+            .filter(|action| action.pointer("/data/card/id").and_then(Value::as_str) == Some(card_id))
+            .map(|action| Annotation {
+                at: timestamp(action, "date").unwrap_or(task.created_at),
+                text: action.pointer("/data/text").and_then(Value::as_str).unwrap_or_default().to_string(),
+            })
+            .collect();
+        comments.sort_by_key(|comment| comment.at);
+        task.annotations = comments;
+        for (field, what) in [("idMembers", "members"), ("attachments", "attachments"), ("customFieldItems", "custom fields")] {
+            if card.get(field).and_then(Value::as_array).is_some_and(|items| !items.is_empty()) {
+                *dropped.entry(what.to_string()).or_default() += 1;
+            }
+        }
+        let parent = task.clone();
+        tasks.push(task);
+        for checklist in checklists.iter().filter(|checklist| field(checklist, "idCard") == card_id) {
+            for item in checklist.get("checkItems").and_then(Value::as_array).into_iter().flatten() {
+                let name = field(item, "name");
+                if name.is_empty() {
+                    continue;
+                }
+                let mut subtask = Task::new(tasks.len() as u32 + 1, name.to_string());
+                subtask.parent = Some(parent.id);
+                subtask.project = parent.project.clone();
+                subtask.created_at = created_from_id(field(item, "id")).unwrap_or(parent.created_at);
+                subtask.due = timestamp(item, "due").and_then(local_date);
+                if field(item, "state") == "complete" || parent.completed_at.is_some() {
+                    subtask.completed_at = parent.updated_at.or(Some(subtask.created_at));
+                }
+                tasks.push(subtask);
+            }
+        }
+    }
+    warn_dropped(&dropped, "card", &mut warnings);
+    Ok(Parsed { tasks, warnings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // card ids start with the creation time in hex, 0x68e0... is in october 2025
+    const BOARD: &str = r#"{
+        "name": "Home Renovation",
+        "lists": [{"id": "l1", "name": "To Do"}, {"id": "l2", "name": "Done"}],
+        "cards": [
+            {"id": "68e0a0000000000000000001", "name": "paint kitchen", "idList": "l1", "desc": "white, matte",
+             "due": "2026-10-20T12:00:00.000Z", "labels": [{"name": "Weekend"}, {"name": "", "color": "green"}], "idMembers": ["m1"]},
+            {"id": "68e0a0000000000000000002", "name": "buy ladder", "idList": "l2"},
+            {"id": "68e0a0000000000000000003", "name": "", "idList": "l1"}
+        ],
+        "checklists": [{"idCard": "68e0a0000000000000000001", "checkItems": [
+            {"id": "c1", "name": "review", "state": "complete"},
+            {"id": "c2", "name": "review", "state": "incomplete"}
+        ]}],
+        "actions": [{"type": "commentCard", "date": "2026-10-02T10:00:00.000Z",
+            "data": {"card": {"id": "68e0a0000000000000000001"}, "text": "get two coats"}}]
+    }"#;
+
+    #[test]
+    fn reads_a_board_export() {
+        let parsed = read(BOARD).unwrap();
+        assert_eq!(parsed.skipped, 1);
+        let [kitchen, first, second, ladder] = &parsed.tasks[..] else {
+            panic!("expected four tasks, got {}", parsed.tasks.len());
+        };
+        assert_eq!(kitchen.project.as_deref(), Some("home-renovation"));
+        assert_eq!(kitchen.tags, ["to-do", "weekend", "green"]);
+        assert_eq!(kitchen.notes.as_deref(), Some("white, matte"));
+        assert_eq!(kitchen.created_at, 0x68e0a000);
+        assert_eq!(kitchen.annotations[0].text, "get two coats");
+        assert!(kitchen.completed_at.is_none() && kitchen.due.is_some());
+        // checklist items with the same name stay separate subtasks
+        assert_eq!((first.description.as_str(), first.parent, first.completed_at.is_some()), ("review", Some(1), true));
+        assert_eq!((second.description.as_str(), second.parent, second.completed_at.is_some()), ("review", Some(1), false));
+        assert_ne!(first.id, second.id);
+        assert!(ladder.completed_at.is_some());
+        assert!(parsed.warnings.contains(&"dropped members from 1 card(s), taskz has no equivalent".to_string()));
+    }
+
+    #[test]
+    fn other_json_is_refused() {
+        assert!(read("{\"name\": \"not a board\"}").is_err());
+        assert!(read("[").is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::io;
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use crate::{Task, get_data_file_path};
+
+pub const MAX_JOURNAL_ENTRIES: usize = 100;
+
+// tasks are boxed so a batch of small operations doesn't take the size of an edit each
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Operation {
+    Add { task: Box<Task> },
+    Edit { before: Box<Task>, after: Box<Task> },
+    Done { task: Box<Task> },
+    Restore { task: Box<Task> },
+    Clear { tasks: Vec<Task> },
+    // several operations made by one command, undone and redone together
+    Batch { operations: Vec<Operation> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub at: i64,
+    pub operation: Operation,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Journal {
+    pub undo: Vec<Entry>,
+    pub redo: Vec<Entry>,
+}
+
+fn same_task(a: &Task, b: &Task) -> bool {
+    if a.id != 0 && b.id != 0 {
+        return a.id == b.id;
+    }
+    a.created_at == b.created_at && a.description == b.description
+}
+
+fn take_task(tasks: &mut Vec<Task>, target: &Task) -> io::Result<Task> {
+    match tasks.iter().rposition(|task| same_task(task, target)) {
+        Some(index) => Ok(tasks.remove(index)),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, format!("journal out of sync: task \"{}\" not found", target.description))),
+    }
+}
+
+// names of the fields an edit changed
+fn changed_fields(before: &Task, after: &Task) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    let mut check = |name: &'static str, changed: bool| if changed { fields.push(name) };
+    check("description", before.description != after.description);
+    check("due", before.due != after.due);
+    check("priority", before.priority != after.priority);
+    check("tags", before.tags != after.tags);
+    check("project", before.project != after.project);
+    check("parent", before.parent != after.parent);
+    check("dependencies", before.depends_on != after.depends_on);
+    check("recurrence", before.recur != after.recur);
+    check("notes", before.notes != after.notes);
+    check("annotations", before.annotations.len() != after.annotations.len());
+    fields
+}
+
+impl Operation {
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Add { task } => format!("add: {}", task.description),
+            Operation::Edit { before, after } if before.description != after.description => format!("edit: {} -> {}", before.description, after.description),
+            Operation::Edit { after, .. } => format!("update: {}", after.description),
+            Operation::Done { task } => format!("done: {}", task.description),
+            Operation::Restore { task } => format!("restore: {}", task.description),
+            Operation::Clear { tasks } => format!("clear: {} task(s)", tasks.len()),
+            Operation::Batch { operations } => match operations.first() {
+                Some(first) if operations.len() > 1 => format!("{} (+{} more)", first.describe(), operations.len() - 1),
+                Some(first) => first.describe(),
+                None => "nothing".to_string(),
+            },
+        }
+    }
+
+    pub fn apply(&self, tasks: &mut Vec<Task>, archive: &mut Vec<Task>) -> io::Result<()> {
+        match self {
+            Operation::Add { task } => tasks.push(Task::clone(task)),
+            Operation::Edit { before, after } => {
+                take_task(tasks, before)?;
+                tasks.push(Task::clone(after));
+            },
+            Operation::Done { task } => {
+                take_task(tasks, task)?;
+                archive.push(Task::clone(task));
+            },
+            Operation::Restore { task } => {
+                take_task(archive, task)?;
+                let mut restored = Task::clone(task);
+                restored.completed_at = None;
+                tasks.push(restored);
+            },
+            Operation::Clear { tasks: cleared } => {
+                tasks.retain(|task| !cleared.iter().any(|c| same_task(task, c)));
+            },
+            Operation::Batch { operations } => {
+                for operation in operations {
+                    operation.apply(tasks, archive)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    pub fn revert(&self, tasks: &mut Vec<Task>, archive: &mut Vec<Task>) -> io::Result<()> {
+        match self {
+            Operation::Add { task } => {
+                take_task(tasks, task)?;
+            },
+            Operation::Edit { before, after } => {
+                take_task(tasks, after)?;
+                tasks.push(Task::clone(before));
+            },
+            Operation::Done { task } => {
+                take_task(archive, task)?;
+                let mut restored = Task::clone(task);
+                restored.completed_at = None;
+                tasks.push(restored);
+            },
+            Operation::Restore { task } => {
+                take_task(tasks, task)?;
+                archive.push(Task::clone(task));
+            },
+            Operation::Clear { tasks: cleared } => tasks.extend(cleared.iter().cloned()),
+            Operation::Batch { operations } => {
+                for operation in operations.iter().rev() {
+                    operation.revert(tasks, archive)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // what this operation did to `task`, if it touched it at all
+    fn event_for(&self, task: &Task) -> Vec<String> {
+        match self {
+            Operation::Add { task: added } if same_task(added, task) => vec!["added".to_string()],
+            Operation::Edit { before, after } if same_task(after, task) => {
+                if before.description != after.description {
+                    vec![format!("edited: {} -> {}", before.description, after.description)]
+                } else {
+                    vec![format!("changed {}", changed_fields(before, after).join(", "))]
+                }
+            },
+            Operation::Done { task: done } if same_task(done, task) => vec!["done".to_string()],
+            Operation::Restore { task: restored } if same_task(restored, task) => vec!["restored".to_string()],
+            Operation::Clear { tasks } if tasks.iter().any(|cleared| same_task(cleared, task)) => vec!["cleared".to_string()],
+            Operation::Batch { operations } => operations.iter().flat_map(|operation| operation.event_for(task)).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+// every journaled change to `task` that can still be undone, oldest first
+pub fn task_history(journal: &Journal, task: &Task) -> Vec<(i64, String)> {
+    journal.undo.iter().flat_map(|entry| {
+        entry.operation.event_for(task).into_iter().map(move |event| (entry.at, event))
+    }).collect()
+}
+
+pub fn load_journal() -> io::Result<Journal> {
+    let path = get_data_file_path("journal.json")?;
+    if !path.exists() {
+        return Ok(migrate_undo_file()?.unwrap_or_default());
+    }
+    let data = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&data).unwrap_or_default())
+}
+
+pub fn save_journal(journal: &Journal) -> io::Result<()> {
+    let path = get_data_file_path("journal.json")?;
+    let data = serde_json::to_string_pretty(journal)?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+// older versions kept a single completed task in undo.json
+fn migrate_undo_file() -> io::Result<Option<Journal>> {
+    let path = get_data_file_path("undo.json")?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(&path)?;
+    let journal = serde_json::from_str::<Task>(&data).ok().map(|task| Journal {
+        undo: vec![Entry { at: task.completed_at.unwrap_or(task.created_at), operation: Operation::Done { task: Box::new(task) } }],
+        redo: vec![],
+    });
+    fs::remove_file(path)?;
+    Ok(journal)
+}
+
+pub fn record(operation: Operation) -> io::Result<()> {
+    let mut journal = load_journal()?;
+    journal.undo.push(Entry { at: Utc::now().timestamp(), operation });
+    if journal.undo.len() > MAX_JOURNAL_ENTRIES {
+        let excess = journal.undo.len() - MAX_JOURNAL_ENTRIES;
+        journal.undo.drain(..excess);
+    }
+    journal.redo.clear();
+    save_journal(&journal)
+}
@@ -1,41 +1,257 @@
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 use std::env;
 use std::fs;
-use std::io;
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::PathBuf;
-use chrono::Utc;
+use std::process::Command;
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Serialize, Deserialize};
-use strsim::levenshtein;
-use colored::Colorize;
+use serde_json::json;
+use colored::{Color, Colorize};
+use regex::{Regex, RegexBuilder};
+use journal::Operation;
+use filter::Filter;
+use formats::{Format, markdown::{self, Group}};
+use dates::Recurrence;
+use matcher::Algorithm;
+
+mod dates;
+mod filter;
+mod formats;
+mod journal;
+mod matcher;
+mod output;
+
+// human-readable output, left out with --json and --jsonl
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if output::is_human() {
+            println!($($arg)*)
+        }
+    };
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Task {
+    #[serde(default)]
+    id: u32,
     description: String,
     created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recur: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Annotation {
+    at: i64,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    #[serde(rename = "L")]
+    Low,
+    #[serde(rename = "M")]
+    Medium,
+    #[serde(rename = "H")]
+    High,
+}
+
+impl Priority {
+    fn parse(name: &str) -> Option<Priority> {
+        match name.to_lowercase().as_str() {
+            "h" | "high" => Some(Priority::High),
+            "m" | "med" | "medium" => Some(Priority::Medium),
+            "l" | "low" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    fn letter(self) -> &'static str {
+        match self {
+            Priority::High => "H",
+            Priority::Medium => "M",
+            Priority::Low => "L",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Priority::High => Color::BrightRed,
+            Priority::Medium => Color::BrightYellow,
+            Priority::Low => Color::BrightBlue,
+        }
+    }
+
+    fn urgency(self) -> f64 {
+        match self {
+            Priority::High => 6.0,
+            Priority::Medium => 3.9,
+            Priority::Low => 1.8,
+        }
+    }
 }
 
 impl Task {
-    fn new(description: String) -> Task {
+    fn new(id: u32, description: String) -> Task {
         Task {
+            id,
             description,
             created_at: Utc::now().timestamp(),
+            updated_at: None,
+            completed_at: None,
+            due: None,
+            priority: None,
+            tags: vec![],
+            project: None,
+            parent: None,
+            depends_on: vec![],
+            recur: None,
+            notes: None,
+            annotations: vec![],
+        }
+    }
+
+    // the copy of a recurring task that replaces it once completed
+    fn next_occurrence(&self, id: u32, today: NaiveDate) -> Option<Task> {
+        let recur = self.recur?;
+        // keep the schedule, but never bring the task back already overdue
+        let mut due = recur.next_after(self.due.unwrap_or(today))?;
+        while due <= today {
+            due = recur.next_after(due)?;
+        }
+        let mut next = Task::new(id, self.description.clone());
+        next.due = Some(due);
+        next.priority = self.priority;
+        next.tags = self.tags.clone();
+        next.project = self.project.clone();
+        next.parent = self.parent;
+        next.recur = self.recur;
+        Some(next)
+    }
+
+    fn add_tag(&mut self, tag: &str) -> bool {
+        if self.tags.iter().any(|existing| existing == tag) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|existing| existing == tag)
+    }
+
+    // priority, how close the due date is and age combined, higher means do it sooner
+    fn urgency(&self, today: NaiveDate) -> f64 {
+        let priority = self.priority.map(Priority::urgency).unwrap_or(0.0);
+        let due = match self.due {
+            Some(due) => {
+                // 0.2 when two or more weeks away, rising to 1.0 once a week overdue
+                let days_left = (due - today).num_days().clamp(-7, 14) as f64;
+                12.0 * (0.2 + (14.0 - days_left) * 0.8 / 21.0)
+            },
+            None => 0.0,
+        };
+        let age_days = (Utc::now().timestamp() - self.created_at).max(0) as f64 / 86400.0;
+        let age = 2.0 * (age_days / 365.0).min(1.0);
+        priority + due + age
+    }
+}
+
+// attributes given inline with a description, like `due:friday`, `!high` or `+work`
+#[derive(Default)]
+struct Attributes {
+    due: Option<NaiveDate>,
+    priority: Option<Priority>,
+    tags: Vec<String>,
+    project: Option<String>,
+    recur: Option<Recurrence>,
+}
+
+// project names are compared case-insensitively, so they are stored lowercased
+fn parse_project(name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+    valid.then_some(name)
+}
+
+// `+work` or `#work` gives "work"; `#12` is an id, not a tag
+fn parse_tag(word: &str) -> Option<String> {
+    let name = word.strip_prefix('+').or_else(|| word.strip_prefix('#'))?;
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        && !name.chars().all(|c| c.is_ascii_digit());
+    valid.then(|| name.to_lowercase())
+}
+
+// pulls tag words out of a description, returning what is left and the tags
+fn split_tags(description: &str) -> (String, Vec<String>) {
+    let mut tags: Vec<String> = Vec::new();
+    let mut words = Vec::new();
+    for word in description.split_whitespace() {
+        match parse_tag(word) {
+            Some(tag) if !tags.contains(&tag) => tags.push(tag),
+            Some(_) => {},
+            None => words.push(word),
         }
     }
+    (words.join(" "), tags)
 }
 
-fn get_tasks_file_path() -> io::Result<PathBuf> {
-    let mut base_dir = if cfg!(target_os = "windows") {
-        PathBuf::from(env::var("LOCALAPPDATA").unwrap_or_else(|_| "C:\\temp".to_string()))
-    } else {
-        let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        PathBuf::from(home).join(".local/share")
-    };
-    base_dir.push("taskz");
-    fs::create_dir_all(&base_dir)?;
-    base_dir.push("tasks.json");
-    Ok(base_dir)
+// splits attribute tokens off the description words
+fn extract_attributes(words: &[String]) -> Result<(String, Attributes), String> {
+    let mut attributes = Attributes::default();
+    let mut description = Vec::new();
+    for word in words {
+        if let Some(value) = word.strip_prefix("due:") {
+            attributes.due = Some(parse_due(value)?);
+        } else if let Some(value) = word.strip_prefix("project:") {
+            attributes.project = Some(parse_project(value).ok_or_else(|| format!("invalid project name \"{}\"", value))?);
+        } else if let Some(value) = word.strip_prefix("recur:") {
+            attributes.recur = Some(Recurrence::parse(value).ok_or_else(|| format!("unknown recurrence \"{}\", try daily, weekly, every-2-weeks or monthly-on-day-15", value))?);
+        } else if let Some(value) = word.strip_prefix("pri:") {
+            attributes.priority = Some(Priority::parse(value).ok_or_else(|| format!("unknown priority \"{}\", expected H, M or L", value))?);
+        } else if let Some(priority) = word.strip_prefix('!').and_then(Priority::parse) {
+            attributes.priority = Some(priority);
+        } else if let Some(tag) = parse_tag(word) {
+            if !attributes.tags.contains(&tag) {
+                attributes.tags.push(tag);
+            }
+        } else {
+            description.push(word.as_str());
+        }
+    }
+    Ok((description.join(" "), attributes))
 }
 
-fn get_undo_file_path() -> io::Result<PathBuf> {
+fn parse_due(value: &str) -> Result<NaiveDate, String> {
+    dates::parse_date(value, today()).ok_or_else(|| format!("could not understand the date \"{}\"", value))
+}
+
+fn get_data_file_path(file_name: &str) -> io::Result<PathBuf> {
     let mut base_dir = if cfg!(target_os = "windows") {
         PathBuf::from(env::var("LOCALAPPDATA").unwrap_or_else(|_| "C:\\temp".to_string()))
     } else {
@@ -44,27 +260,209 @@ fn get_undo_file_path() -> io::Result<PathBuf> {
     };
     base_dir.push("taskz");
     fs::create_dir_all(&base_dir)?;
-    base_dir.push("undo.json");
+    base_dir.push(file_name);
     Ok(base_dir)
 }
 
-fn load_tasks() -> io::Result<Vec<Task>> {
-    let path = get_tasks_file_path()?;
+fn get_tasks_file_path() -> io::Result<PathBuf> {
+    get_data_file_path("tasks.json")
+}
+
+fn get_archive_file_path() -> io::Result<PathBuf> {
+    get_data_file_path("archive.json")
+}
+
+fn read_task_file(path: &PathBuf) -> io::Result<Vec<Task>> {
     if !path.exists() {
         return Ok(vec![]);
     }
-    let data = fs::read_to_string(&path)?;
+    let data = fs::read_to_string(path)?;
     let tasks: Vec<Task> = serde_json::from_str(&data).unwrap_or_else(|_| vec![]);
     Ok(tasks)
 }
 
-fn save_tasks(tasks: &Vec<Task>) -> io::Result<()> {
-    let path = get_tasks_file_path()?;
+fn load_task_file(path: PathBuf) -> io::Result<Vec<Task>> {
+    let mut tasks = read_task_file(&path)?;
+    let mut changed = false;
+    for task in tasks.iter_mut() {
+        // tasks saved before ids existed get one the first time they are loaded
+        if task.id == 0 {
+            task.id = next_task_id()?;
+            changed = true;
+        }
+        // so do tags that used to be written straight into the description
+        if task.tags.is_empty() && task.description.split_whitespace().any(|word| parse_tag(word).is_some()) {
+            let (description, tags) = split_tags(&task.description);
+            task.description = description;
+            task.tags = tags;
+            changed = true;
+        }
+    }
+    if changed {
+        save_task_file(path, &tasks)?;
+    }
+    Ok(tasks)
+}
+
+fn save_task_file(path: PathBuf, tasks: &[Task]) -> io::Result<()> {
     let data = serde_json::to_string_pretty(tasks)?;
     fs::write(path, data)?;
     Ok(())
 }
 
+// TASKZ_TODO_TXT points taskz at a todo.txt file instead of tasks.json and archive.json
+fn todo_txt_path() -> Option<PathBuf> {
+    env::var_os("TASKZ_TODO_TXT").filter(|path| !path.is_empty()).map(PathBuf::from)
+}
+
+fn read_todo_txt(path: &PathBuf) -> io::Result<Vec<Task>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(formats::todotxt::read(&data).tasks)
+}
+
+fn write_todo_txt(path: &PathBuf, tasks: &[Task]) -> io::Result<()> {
+    fs::write(path, formats::todotxt::write(tasks))
+}
+
+// open lines are the task list and "x" lines the archive, both in the one file
+fn load_todo_txt(path: &PathBuf, done: bool) -> io::Result<Vec<Task>> {
+    let mut tasks = read_todo_txt(path)?;
+    // lines added by other todo.txt apps get an id the first time they are loaded
+    if tasks.iter().any(|task| task.id == 0) {
+        for task in tasks.iter_mut().filter(|task| task.id == 0) {
+            task.id = next_task_id()?;
+        }
+        write_todo_txt(path, &tasks)?;
+    }
+    Ok(tasks.into_iter().filter(|task| task.completed_at.is_some() == done).collect())
+}
+
+// rewrites one half of the file and keeps the other half as it is on disk
+fn save_todo_txt(path: &PathBuf, tasks: &[Task], done: bool) -> io::Result<()> {
+    let others: Vec<Task> = read_todo_txt(path)?.into_iter().filter(|task| task.completed_at.is_some() != done).collect();
+    let mut tasks: Vec<Task> = tasks.to_vec();
+    for task in tasks.iter_mut() {
+        if done && task.completed_at.is_none() {
+            task.completed_at = Some(task.created_at);
+        }
+    }
+    let all = if done { [others, tasks].concat() } else { [tasks, others].concat() };
+    write_todo_txt(path, &all)
+}
+
+fn load_tasks() -> io::Result<Vec<Task>> {
+    match todo_txt_path() {
+        Some(path) => load_todo_txt(&path, false),
+        None => load_task_file(get_tasks_file_path()?),
+    }
+}
+
+fn save_tasks(tasks: &[Task]) -> io::Result<()> {
+    match todo_txt_path() {
+        Some(path) => save_todo_txt(&path, tasks, false),
+        None => save_task_file(get_tasks_file_path()?, tasks),
+    }
+}
+
+fn load_archive() -> io::Result<Vec<Task>> {
+    match todo_txt_path() {
+        Some(path) => load_todo_txt(&path, true),
+        None => load_task_file(get_archive_file_path()?),
+    }
+}
+
+fn save_archive(archive: &[Task]) -> io::Result<()> {
+    match todo_txt_path() {
+        Some(path) => save_todo_txt(&path, archive, true),
+        None => save_task_file(get_archive_file_path()?, archive),
+    }
+}
+
+fn next_task_id() -> io::Result<u32> {
+    let path = get_data_file_path("next_id")?;
+    let next = match fs::read_to_string(&path).ok().and_then(|data| data.trim().parse::<u32>().ok()) {
+        Some(next) => next,
+        None => {
+            let existing = match todo_txt_path() {
+                Some(path) => read_todo_txt(&path)?,
+                None => [read_task_file(&get_tasks_file_path()?)?, read_task_file(&get_archive_file_path()?)?].concat(),
+            };
+            existing.iter().map(|task| task.id).max().unwrap_or(0) + 1
+        }
+    };
+    fs::write(&path, (next + 1).to_string())?;
+    Ok(next)
+}
+
+fn parse_task_id(query: &str) -> Option<u32> {
+    query.trim().strip_prefix('#')?.parse().ok()
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+fn format_task_body(task: &Task) -> String {
+    let mut body = task.description.clone();
+    if let Some(due) = task.due {
+        body.push_str(&format!(" (due {})", dates::format_date(due)));
+    }
+    if let Some(recur) = task.recur {
+        body.push_str(&format!(" \u{21bb} {}", recur));
+    }
+    body
+}
+
+fn format_tags(task: &Task, show_project: bool) -> String {
+    let mut tags: String = task.tags.iter().map(|tag| format!(" +{}", tag)).collect();
+    if let (Some(project), true) = (&task.project, show_project) {
+        tags.push_str(&format!(" project:{}", project));
+    }
+    tags
+}
+
+fn format_task(task: &Task) -> String {
+    match task.priority {
+        Some(priority) => format!("[#{}] ({}) {}{}", task.id, priority.letter(), format_task_body(task), format_tags(task, true)),
+        None => format!("[#{}] {}{}", task.id, format_task_body(task), format_tags(task, true)),
+    }
+}
+
+fn print_task(task: &Task) {
+    say!("{}", render_task(task, true));
+}
+
+// open tasks are red once overdue and yellow on the day they are due
+fn due_color(task: &Task) -> (Color, &'static str) {
+    match task.due {
+        Some(due) if task.completed_at.is_none() && due < today() => (Color::Red, " overdue"),
+        Some(due) if task.completed_at.is_none() && due == today() => (Color::Yellow, ""),
+        _ => (Color::Cyan, ""),
+    }
+}
+
+// the priority marker keeps its own color
+fn render_task(task: &Task, show_project: bool) -> String {
+    let (color, suffix) = due_color(task);
+    let id = format!("[#{}]", task.id).color(color);
+    let body = format!("{}{}", format_task_body(task), suffix).color(color);
+    let tags = format_tags(task, show_project).dimmed();
+    match task.priority {
+        Some(priority) => format!("{} {} {}{}", id, format!("({})", priority.letter()).color(priority.color()).bold(), body, tags),
+        None => format!("{} {}{}", id, body, tags),
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    match DateTime::from_timestamp(timestamp, 0) {
+        Some(time) => time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
 fn install() -> io::Result<()> {
     let current_exe = env::current_exe()?;
     let target_path = if cfg!(target_os = "windows") {
@@ -72,11 +470,10 @@ fn install() -> io::Result<()> {
     } else {
         PathBuf::from("/usr/local/bin/taskz")
     };
-    fs::copy(&current_exe, &target_path).map_err(|e| {
-        eprintln!("{}", "run as administrator".red());
-        e
+    fs::copy(&current_exe, &target_path).inspect_err(|_| {
+        output::error("invalid_input", "run as administrator");
     })?;
-    println!("{}", format!("installed successfully to {:?}", target_path).green());
+    say!("{}", format!("installed successfully to {:?}", target_path).green());
     Ok(())
 }
 
@@ -87,211 +484,2351 @@ fn uninstall() -> io::Result<()> {
         PathBuf::from("/usr/local/bin/taskz")
     };
     if target_path.exists() {
-        fs::remove_file(&target_path).map_err(|e| {
-            eprintln!("{}", "run as administrator".red());
-            e
+        fs::remove_file(&target_path).inspect_err(|_| {
+            output::error("invalid_input", "run as administrator");
         })?;
-        println!("{}", format!("uninstalled successfully from {:?}", target_path).green());
+        say!("{}", format!("uninstalled successfully from {:?}", target_path).green());
     } else {
-        println!("{}", "no installation found".red());
+        say!("{}", "no installation found".red());
     }
     Ok(())
 }
 
-fn add_task(description: String) -> io::Result<()> {
+// stamps the change made to `tasks[index]`, saves it and journals it
+fn save_edit(tasks: &mut [Task], index: usize, before: Task) -> io::Result<Task> {
+    tasks[index].updated_at = Some(Utc::now().timestamp());
+    let after = tasks[index].clone();
+    save_tasks(tasks)?;
+    journal::record(Operation::Edit { before: Box::new(before), after: Box::new(after.clone()) })?;
+    output::task("edited", &after);
+    Ok(after)
+}
+
+fn add_task(description: String, attributes: Attributes, parent_query: Option<String>, options: &MatchOptions) -> io::Result<()> {
     let mut tasks = load_tasks()?;
-    tasks.push(Task::new(description));
+    let parent = match parent_query {
+        Some(query) => match select_task(&tasks, &query, options)? {
+            Some(index) => Some(tasks[index].clone()),
+            None => return Ok(()),
+        },
+        None => None,
+    };
+    let mut task = Task::new(next_task_id()?, description);
+    task.due = attributes.due;
+    task.priority = attributes.priority;
+    task.tags = attributes.tags;
+    // subtasks live in their parent's project unless told otherwise
+    task.project = attributes.project.or_else(|| parent.as_ref().and_then(|parent| parent.project.clone()));
+    task.parent = parent.as_ref().map(|parent| parent.id);
+    task.recur = attributes.recur;
+    tasks.push(task.clone());
     save_tasks(&tasks)?;
-    println!("{}", "task added".green());
+    journal::record(Operation::Add { task: Box::new(task.clone()) })?;
+    output::task("added", &task);
+    match parent {
+        Some(parent) => say!("{}", format!("subtask added under: {}", parent.description).green()),
+        None => say!("{}", "task added".green()),
+    }
     Ok(())
 }
 
-fn list_tasks(alphabetical: bool) -> io::Result<()> {
-    let mut tasks = load_tasks()?;
-    if alphabetical {
-        tasks.sort_by(|a, b| a.description.to_lowercase().cmp(&b.description.to_lowercase()));
-    } else {
-        tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+// ids of every open task below `id`, deepest first
+fn open_descendants(tasks: &[Task], id: u32) -> Vec<u32> {
+    let mut descendants = Vec::new();
+    collect_descendants(tasks, id, &mut vec![id], &mut descendants);
+    descendants
+}
+
+// `seen` stops parents that loop back on themselves from recursing forever
+fn collect_descendants(tasks: &[Task], id: u32, seen: &mut Vec<u32>, descendants: &mut Vec<u32>) {
+    for child in tasks.iter().filter(|task| task.parent == Some(id)) {
+        if seen.contains(&child.id) {
+            continue;
+        }
+        seen.push(child.id);
+        collect_descendants(tasks, child.id, seen, descendants);
+        descendants.push(child.id);
+    }
+}
+
+// "3/5 done" for tasks that have subtasks
+fn subtask_progress(task: &Task, tasks: &[Task], archive: &[Task]) -> Option<String> {
+    let open = tasks.iter().filter(|child| child.parent == Some(task.id)).count();
+    let done = archive.iter().filter(|child| child.parent == Some(task.id)).count();
+    (open + done > 0).then(|| format!(" {}/{} done", done, open + done))
+}
+
+#[derive(Clone, Copy)]
+enum SortOrder {
+    Created,
+    Alphabetical,
+    Due,
+    Urgency,
+}
+
+impl SortOrder {
+    fn parse(name: &str) -> Option<SortOrder> {
+        match name {
+            "created" | "age" => Some(SortOrder::Created),
+            "alpha" | "alphabetical" | "description" => Some(SortOrder::Alphabetical),
+            "due" => Some(SortOrder::Due),
+            "urgency" => Some(SortOrder::Urgency),
+            _ => None,
+        }
+    }
+}
+
+// open tasks (and completed ones when the filter asks for them) matching `filter`
+fn filtered_tasks(filter: &Filter, project: Option<&str>, all_fields: bool) -> io::Result<Vec<Task>> {
+    let tasks = load_tasks()?;
+    let archive = if filter.wants_archive() { load_archive()? } else { vec![] };
+    Ok(filter_tasks(&tasks, &archive, filter, project, all_fields))
+}
+
+// the archive only takes part when the filter asks about completed tasks
+fn filter_tasks(tasks: &[Task], archive: &[Task], filter: &Filter, project: Option<&str>, all_fields: bool) -> Vec<Task> {
+    let archive = if filter.wants_archive() { archive } else { &[] };
+    let context = filter::Context { tasks, today: today(), all_fields };
+    tasks.iter().chain(archive.iter()).filter(|task| {
+        project.is_none_or(|project| task.project.as_deref() == Some(project)) && filter.matches(task, &context)
+    }).cloned().collect()
+}
+
+fn list_tasks(sort: SortOrder, filter: &Filter, project: Option<&str>) -> io::Result<()> {
+    let all_tasks = load_tasks()?;
+    let archive = load_archive()?;
+    let mut tasks = filter_tasks(&all_tasks, &archive, filter, project, false);
+    tasks.sort_by_key(|task| (task.created_at, task.id));
+    match sort {
+        SortOrder::Created => {},
+        SortOrder::Alphabetical => tasks.sort_by_key(|task| task.description.to_lowercase()),
+        // tasks without a due date go last
+        SortOrder::Due => tasks.sort_by_key(|task| (task.due.is_none(), task.due)),
+        SortOrder::Urgency => {
+            let today = today();
+            tasks.sort_by(|a, b| b.urgency(today).total_cmp(&a.urgency(today)));
+        },
+    }
+    for task in &tasks {
+        output::task("task", task);
     }
     if tasks.is_empty() {
-        println!("{}", "no tasks found".red());
-    } else {
-        for task in tasks {
-            println!("{}", format!("[{}] {}", task.created_at, task.description).cyan());
+        say!("{}", "no tasks found".red());
+        return Ok(());
+    }
+    let print = |group: Vec<&Task>| {
+        print_task_tree(&group, &all_tasks, &archive, sort);
+    };
+    // without a project selected, tasks are grouped under their project's name
+    if project.is_some() || tasks.iter().all(|task| task.project.is_none()) {
+        print(tasks.iter().collect());
+        return Ok(());
+    }
+    let mut projects: Vec<Option<&String>> = tasks.iter().map(|task| task.project.as_ref()).collect();
+    projects.sort();
+    projects.dedup();
+    for (i, name) in projects.iter().enumerate() {
+        if i > 0 {
+            say!();
         }
+        say!("{}", name.map(|name| name.as_str()).unwrap_or("(no project)").bold());
+        print(tasks.iter().filter(|task| task.project.as_ref() == *name).collect());
     }
     Ok(())
 }
 
-fn search_tasks(query: String) -> io::Result<()> {
-    let tasks = load_tasks()?;
-    let query_lower = query.to_lowercase();
-    let filtered: Vec<&Task> = tasks.iter().filter(|task| task.description.to_lowercase().contains(&query_lower)).collect();
+// subtasks are indented under their parent; a subtask whose parent is not
+// shown is listed at the top level
+fn print_task_tree(shown: &[&Task], tasks: &[Task], archive: &[Task], sort: SortOrder) {
+    let is_shown = |id: u32| shown.iter().any(|task| task.id == id);
+    let mut printed = Vec::new();
+    for root in shown.iter().filter(|task| !task.parent.is_some_and(is_shown)) {
+        print_subtree(root, 0, shown, tasks, archive, sort, &mut printed);
+    }
+    // tasks whose parents loop back to them have no root, so they start their own tree
+    for task in shown {
+        print_subtree(task, 0, shown, tasks, archive, sort, &mut printed);
+    }
+}
+
+fn print_subtree(task: &Task, depth: usize, shown: &[&Task], tasks: &[Task], archive: &[Task], sort: SortOrder, printed: &mut Vec<u32>) {
+    if printed.contains(&task.id) {
+        return;
+    }
+    printed.push(task.id);
+    let mut line = "  ".repeat(depth);
+    if let SortOrder::Urgency = sort {
+        line.push_str(&format!("{} ", format!("{:>5.1}", task.urgency(today())).dimmed()));
+    }
+    // every listed task already sits under its project's heading
+    line.push_str(&render_task(task, false));
+    if let Some(progress) = subtask_progress(task, tasks, archive) {
+        line.push_str(&progress.dimmed().to_string());
+    }
+    say!("{}", line);
+    for child in shown.iter().filter(|child| child.parent == Some(task.id)) {
+        print_subtree(child, depth + 1, shown, tasks, archive, sort, printed);
+    }
+}
+
+fn search_tasks(query: &str, filter: &Filter, all_fields: bool, project: Option<&str>) -> io::Result<()> {
+    let filtered = filtered_tasks(filter, project, all_fields)?;
     if filtered.is_empty() {
-        println!("{}", format!("no tasks found matching \"{}\"", query).red());
+        say!("{}", format!("no tasks found matching \"{}\"", query).red());
     } else {
-        for task in filtered {
-            println!("{}", format!("[{}] {}", task.created_at, task.description).cyan());
+        for task in &filtered {
+            output::task("task", task);
+            print_task(task);
         }
     }
     Ok(())
 }
 
-fn find_closest_task(tasks: &[Task], query: &str) -> Option<usize> {
-    tasks.iter().enumerate().min_by_key(|(_, task)| levenshtein(&task.description.to_lowercase(), &query.to_lowercase())).map(|(i, _)| i)
+// `--regex`, `--word` and `--case-sensitive` turn the whole query into one pattern
+fn build_pattern(query: &str, regex: bool, word: bool, case_sensitive: bool) -> Result<Regex, String> {
+    let mut pattern = if regex { query.to_string() } else { regex::escape(query) };
+    if regex {
+        if let Err(e) = regex_syntax::Parser::new().parse(query) {
+            return Err(describe_regex_error(query, &e));
+        }
+    }
+    if word {
+        // a boundary only fits next to a word character, `c++` ends without one
+        let word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let (start, end) = if regex { (true, true) } else { (word_char(query.chars().next()), word_char(query.chars().next_back())) };
+        pattern = format!("{}(?:{}){}", if start { r"\b" } else { "" }, pattern, if end { r"\b" } else { "" });
+    }
+    RegexBuilder::new(&pattern).case_insensitive(!case_sensitive).build().map_err(|e| e.to_string())
 }
 
-fn mark_done(query: String) -> io::Result<()> {
-    let mut tasks = load_tasks()?;
-    if let Some(index) = find_closest_task(&tasks, &query) {
-        let removed = tasks.remove(index);
-        save_tasks(&tasks)?;
-        let undo_path = get_undo_file_path()?;
-        let data = serde_json::to_string_pretty(&removed)?;
-        fs::write(undo_path, data)?;
-        println!("{}", format!("task done and removed: {}", removed.description).green());
-    } else {
-        println!("{}", "no matching task found".red());
+// points at the offending part of the pattern below it
+fn describe_regex_error(pattern: &str, error: &regex_syntax::Error) -> String {
+    let (span, kind) = match error {
+        regex_syntax::Error::Parse(e) => (e.span(), e.kind().to_string()),
+        regex_syntax::Error::Translate(e) => (e.span(), e.kind().to_string()),
+        _ => return format!("invalid regex: {}", error),
+    };
+    let column = pattern[..span.start.offset].chars().count();
+    let width = pattern[span.start.offset..span.end.offset].chars().count().max(1);
+    format!("invalid regex at position {}: {}\n  {}\n  {}{}", column + 1, kind, pattern, " ".repeat(column), "^".repeat(width))
+}
+
+// `text` in `color`, with every match of `pattern` highlighted
+fn highlight(text: &str, pattern: &Regex, color: Color) -> String {
+    let plain = |part: &str| if part.is_empty() { String::new() } else { part.color(color).to_string() };
+    let mut highlighted = String::new();
+    let mut last = 0;
+    for found in pattern.find_iter(text).filter(|found| !found.is_empty()) {
+        highlighted.push_str(&plain(&text[last..found.start()]));
+        highlighted.push_str(&found.as_str().black().on_yellow().to_string());
+        last = found.end();
     }
-    Ok(())
+    highlighted.push_str(&plain(&text[last..]));
+    highlighted
 }
 
-fn undo_last() -> io::Result<()> {
-    let undo_path = get_undo_file_path()?;
-    if !undo_path.exists() {
-        println!("{}", "no undo available".red());
-        return Ok(());
+fn search_pattern(query: &str, pattern: &Regex, all_fields: bool, project: Option<&str>) -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let mut found = false;
+    for task in tasks.iter().filter(|task| project.is_none_or(|project| task.project.as_deref() == Some(project))) {
+        let mut lines: Vec<&str> = Vec::new();
+        if all_fields {
+            lines.extend(task.notes.iter().flat_map(|notes| notes.lines()).filter(|line| pattern.is_match(line)));
+            lines.extend(task.annotations.iter().map(|annotation| annotation.text.as_str()).filter(|text| pattern.is_match(text)));
+        }
+        if !pattern.is_match(&task.description) && !task.tags.iter().any(|tag| pattern.is_match(tag)) && lines.is_empty() {
+            continue;
+        }
+        found = true;
+        output::task("task", task);
+        let (color, suffix) = due_color(task);
+        let mut line = format!("[#{}] ", task.id).color(color).to_string();
+        if let Some(priority) = task.priority {
+            line.push_str(&format!("{} ", format!("({})", priority.letter()).color(priority.color()).bold()));
+        }
+        line.push_str(&highlight(&task.description, pattern, color));
+        let rest = format!("{}{}", &format_task_body(task)[task.description.len()..], suffix);
+        if !rest.is_empty() {
+            line.push_str(&rest.color(color).to_string());
+        }
+        for tag in &task.tags {
+            line.push_str(&format!(" {}{}", "+".bright_black(), highlight(tag, pattern, Color::BrightBlack)));
+        }
+        if let Some(project) = &task.project {
+            line.push_str(&format!(" project:{}", project).dimmed().to_string());
+        }
+        say!("{}", line);
+        for text in lines {
+            say!("    {}", highlight(text, pattern, Color::BrightBlack));
+        }
+    }
+    if !found {
+        say!("{}", format!("no tasks found matching \"{}\"", query).red());
     }
-    let data = fs::read_to_string(&undo_path)?;
-    let last_task: Task = serde_json::from_str(&data).unwrap_or_else(|_| {
-        println!("{}", "failed to parse undo data".red());
-        std::process::exit(1);
-    });
-    let mut tasks = load_tasks()?;
-    tasks.push(last_task.clone());
-    save_tasks(&tasks)?;
-    fs::remove_file(undo_path)?;
-    println!("{}", "undo successful: task restored".green());
     Ok(())
 }
 
-fn edit_task(query: String, new_description: String) -> io::Result<()> {
-    let mut tasks = load_tasks()?;
-    if let Some(index) = find_closest_task(&tasks, &query) {
-        tasks[index].description = new_description.clone();
-        save_tasks(&tasks)?;
-        println!("{}", format!("task updated to: {}", new_description).green());
+fn count_tasks(filter: &Filter, project: Option<&str>) -> io::Result<()> {
+    let count = filtered_tasks(filter, project, false)?.len();
+    output::record("count", json!({ "count": count }));
+    say!("{}", count);
+    Ok(())
+}
+
+const DEFAULT_ACCEPT_THRESHOLD: f64 = 0.6;
+const DEFAULT_CONFIRM_THRESHOLD: f64 = 0.3;
+const AMBIGUITY_MARGIN: f64 = 0.05;
+const MAX_CANDIDATES: usize = 5;
+const DEFAULT_EXPLAIN_TOP: usize = 5;
+const DEFAULT_FUZZY_TOP: usize = 10;
+
+struct MatchOptions {
+    algorithm: Algorithm,
+    // only tasks in this project are considered for fuzzy matches
+    project: Option<String>,
+    assume_yes: bool,
+    accept_threshold: f64,
+    confirm_threshold: f64,
+}
+
+impl MatchOptions {
+    fn from_env(algorithm: Option<Algorithm>, project: Option<String>, assume_yes: bool) -> MatchOptions {
+        let threshold = |name: &str, default: f64| {
+            env::var(name).ok().and_then(|value| value.parse::<f64>().ok()).filter(|value| (0.0..=1.0).contains(value)).unwrap_or(default)
+        };
+        let accept_threshold = threshold("TASKZ_MATCH_THRESHOLD", DEFAULT_ACCEPT_THRESHOLD);
+        let confirm_threshold = threshold("TASKZ_CONFIRM_THRESHOLD", DEFAULT_CONFIRM_THRESHOLD).min(accept_threshold);
+        let algorithm = algorithm
+            .or_else(|| env::var("TASKZ_MATCHER").ok().and_then(|name| Algorithm::parse(&name)))
+            .unwrap_or(Algorithm::Smart);
+        MatchOptions { algorithm, project, assume_yes, accept_threshold, confirm_threshold }
+    }
+}
+
+// every task index in scope paired with its score between 0.0 and 1.0, best match first
+fn rank_tasks(tasks: &[Task], query: &str, options: &MatchOptions) -> Vec<(usize, f64)> {
+    let mut ranked: Vec<(usize, f64)> = tasks.iter().enumerate()
+        .filter(|(_, task)| options.project.is_none() || task.project == options.project)
+        .map(|(i, task)| (i, matcher::score(&task.description, query, options.algorithm)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(tasks[a.0].id.cmp(&tasks[b.0].id)));
+    ranked
+}
+
+fn pick_candidate(tasks: &[Task], query: &str, candidates: &[(usize, f64)]) -> io::Result<Option<usize>> {
+    let interactive = interactive();
+    if interactive {
+        say!("{}", format!("several tasks match \"{}\" equally well:", query).yellow());
     } else {
-        println!("{}", "no matching task found".red());
+        output::problem("ambiguous", format!("\"{}\" is ambiguous; use an id to pick one of:", query));
+    }
+    for (n, (index, score)) in candidates.iter().enumerate() {
+        output::record("candidate", json!({ "task": tasks[*index], "score": score }));
+        say!("{}", format!("  {}) {} (score {:.2})", n + 1, format_task(&tasks[*index]), score).cyan());
+    }
+    if !interactive {
+        return Ok(None);
+    }
+    print!("pick a task [1-{}, enter to cancel]: ", candidates.len());
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    match answer.trim().parse::<usize>() {
+        Ok(n) if (1..=candidates.len()).contains(&n) => Ok(Some(candidates[n - 1].0)),
+        _ => {
+            output::problem("cancelled", "cancelled");
+            Ok(None)
+        }
     }
-    Ok(())
 }
 
-fn clear_tasks() -> io::Result<()> {
-    save_tasks(&Vec::<Task>::new())?;
-    println!("{}", "all tasks cleared".green());
-    Ok(())
+// prompts only make sense on a terminal and would corrupt --json output
+fn interactive() -> bool {
+    io::stdin().is_terminal() && output::is_human()
 }
 
-fn print_help() {
-    println!("taskz - ultimate minimalistic todo list app in rust");
-    println!();
-    println!("usage:");
-    println!("  taskz -i                    install the app globally");
-    println!("  taskz -u                    uninstall the app");
-    println!("  taskz add <task>            add a new task");
-    println!("  taskz list [-a]             list tasks (use -a for alphabetical order)");
-    println!("  taskz search <query>        search for tasks containing the query");
-    println!("  taskz done <task>           mark the task as done (and remove it)");
-    println!("  taskz undo                  undo the last removal");
-    println!("  taskz edit <old> /// <new>  edit a task");
-    println!("  taskz clear                 clear all tasks");
-    println!("  taskz /? | -? | -h          show this help");
-    println!();
-    println!("made by tra1an.com");
+fn confirm(prompt: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("{}", "no command provided. usage: taskz [options]".red());
-        return;
+enum Resolution {
+    Empty,
+    MissingId(u32),
+    ById(usize),
+    Accepted(usize, f64),
+    Weak(usize, f64),
+    Ambiguous(Vec<(usize, f64)>),
+    Refused(usize, f64),
+}
+
+// decides what a query selects without prompting or printing anything
+fn resolve_query(tasks: &[Task], query: &str, options: &MatchOptions) -> Resolution {
+    if let Some(id) = parse_task_id(query) {
+        return match tasks.iter().position(|task| task.id == id) {
+            Some(index) => Resolution::ById(index),
+            None => Resolution::MissingId(id),
+        };
     }
-    match args[1].as_str() {
-        "-i" => {
-            if let Err(e) = install() {
-                eprintln!("{}", format!("installation failed: {}", e).red());
-            }
-        },
-        "-u" => {
-            if let Err(e) = uninstall() {
-                eprintln!("{}", format!("uninstallation failed: {}", e).red());
-            }
+    let ranked = rank_tasks(tasks, query, options);
+    let Some(&(index, score)) = ranked.first() else {
+        return Resolution::Empty;
+    };
+    if score < options.confirm_threshold {
+        return Resolution::Refused(index, score);
+    }
+    let candidates: Vec<(usize, f64)> = ranked.iter().take_while(|(_, other)| score - other <= AMBIGUITY_MARGIN).take(MAX_CANDIDATES).copied().collect();
+    if candidates.len() > 1 {
+        Resolution::Ambiguous(candidates)
+    } else if score >= options.accept_threshold || options.assume_yes {
+        Resolution::Accepted(index, score)
+    } else {
+        Resolution::Weak(index, score)
+    }
+}
+
+fn select_task(tasks: &[Task], query: &str, options: &MatchOptions) -> io::Result<Option<usize>> {
+    match resolve_query(tasks, query, options) {
+        Resolution::Empty => {
+            output::problem("no_match", "no matching task found");
+            Ok(None)
         },
-        "add" => {
-            if args.len() < 3 {
-                eprintln!("{}", "please provide a task description".red());
-                return;
-            }
-            let description = args[2..].join(" ");
-            if let Err(e) = add_task(description) {
-                eprintln!("{}", format!("failed to add task: {}", e).red());
-            }
+        Resolution::MissingId(id) => {
+            output::problem("not_found", format!("no task with id #{}", id));
+            Ok(None)
         },
-        "list" => {
-            let alphabetical = args.contains(&"-a".to_string());
-            if let Err(e) = list_tasks(alphabetical) {
-                eprintln!("{}", format!("failed to list tasks: {}", e).red());
-            }
+        Resolution::ById(index) | Resolution::Accepted(index, _) => Ok(Some(index)),
+        Resolution::Ambiguous(candidates) => pick_candidate(tasks, query, &candidates),
+        Resolution::Refused(index, score) => {
+            output::problem("no_match", format!("no close match for \"{}\" (best was \"{}\", score {:.2})", query, tasks[index].description, score));
+            Ok(None)
         },
-        "search" => {
-            if args.len() < 3 {
-                eprintln!("{}", "please provide a search query".red());
-                return;
+        Resolution::Weak(index, score) => {
+            let description = &tasks[index].description;
+            if !interactive() {
+                output::problem("weak_match", format!("\"{}\" is only a weak match (score {:.2}); rerun with --yes before the command to accept it", description, score));
+                return Ok(None);
             }
-            let query = args[2..].join(" ");
-            if let Err(e) = search_tasks(query) {
-                eprintln!("{}", format!("failed to search tasks: {}", e).red());
+            if confirm(&format!("did you mean \"{}\"? (score {:.2})", description, score).yellow().to_string())? {
+                Ok(Some(index))
+            } else {
+                output::problem("cancelled", "cancelled");
+                Ok(None)
             }
         },
-        "done" => {
-            if args.len() < 3 {
-                eprintln!("{}", "please provide the task to mark as done".red());
-                return;
+    }
+}
+
+// what `done` or `edit` would do with the query
+fn describe_verdict(tasks: &[Task], query: &str, options: &MatchOptions) -> String {
+    match resolve_query(tasks, query, options) {
+        Resolution::Empty => "no tasks to match against".to_string(),
+        Resolution::MissingId(id) => format!("no task with id #{}", id),
+        Resolution::ById(index) => format!("selects {} by id", format_task(&tasks[index])),
+        Resolution::Accepted(index, score) => format!("selects {} (score {:.2})", format_task(&tasks[index]), score),
+        Resolution::Weak(index, score) => format!("asks to confirm {} (score {:.2})", format_task(&tasks[index]), score),
+        Resolution::Ambiguous(candidates) => format!("ambiguous between {} tasks, asks which one", candidates.len()),
+        Resolution::Refused(..) => "refused, no close match".to_string(),
+    }
+}
+
+// every task ranked by the same score `done` uses, best first
+fn fuzzy_search(query: String, top: usize, min_score: f64, options: &MatchOptions) -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let ranked: Vec<(usize, f64)> = rank_tasks(&tasks, &query, options).into_iter()
+        .filter(|(_, score)| *score >= min_score)
+        .take(top)
+        .collect();
+    if ranked.is_empty() {
+        say!("{}", format!("no tasks scored at least {:.2} for \"{}\"", min_score, query).red());
+        return Ok(());
+    }
+    for (index, score) in ranked {
+        output::record("match", json!({ "task": tasks[index], "score": score }));
+        // green would be picked outright, yellow would be confirmed first
+        let label = format!("{:.2}", score);
+        let label = if score >= options.accept_threshold {
+            label.green()
+        } else if score >= options.confirm_threshold {
+            label.yellow()
+        } else {
+            label.dimmed()
+        };
+        say!("{} {}", label, render_task(&tasks[index], true));
+    }
+    say!("{}", format!("done would: {}", describe_verdict(&tasks, &query, options)).dimmed());
+    Ok(())
+}
+
+fn explain_match(query: String, top: usize, options: &MatchOptions) -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let verdict = describe_verdict(&tasks, &query, options);
+    let algorithm = match options.algorithm {
+        Algorithm::Smart => "smart",
+        Algorithm::Legacy => "legacy",
+    };
+    output::record("verdict", json!({ "query": query, "matcher": algorithm, "verdict": verdict }));
+    say!("{}", format!("\"{}\" with the {} matcher: {}", query, algorithm, verdict).yellow());
+    if parse_task_id(&query).is_some() {
+        return Ok(());
+    }
+    say!("{}", format!("thresholds: accept >= {:.2}, confirm >= {:.2}", options.accept_threshold, options.confirm_threshold).dimmed());
+    for (n, (index, _)) in rank_tasks(&tasks, &query, options).into_iter().take(top).enumerate() {
+        let explanation = matcher::explain(&tasks[index].description, &query, options.algorithm);
+        say!("{}", format!("{:>2}) {}", n + 1, format_task(&tasks[index])).cyan());
+        let source = match options.algorithm {
+            Algorithm::Legacy => "whole-string levenshtein",
+            Algorithm::Smart if explanation.total > explanation.blend => "phrase hit",
+            Algorithm::Smart => "weighted blend",
+        };
+        say!("    score {:.3} from {}, levenshtein distance {}", explanation.total, source, explanation.distance);
+        let components: Vec<String> = explanation.components.iter().map(|component| {
+            if component.weight == 0.0 {
+                format!("{} {:.2}", component.name, component.value)
+            } else {
+                format!("{} {:.2} x{:.2} = {:.3}", component.name, component.value, component.weight, component.value * component.weight)
+            }
+        }).collect();
+        say!("{}", format!("    {}", components.join(" | ")).dimmed());
+        let components: serde_json::Map<String, serde_json::Value> = explanation.components.iter()
+            .map(|component| (component.name.to_string(), json!({ "value": component.value, "weight": component.weight })))
+            .collect();
+        output::record("candidate", json!({
+            "task": tasks[index],
+            "score": explanation.total,
+            "distance": explanation.distance,
+            "components": components,
+        }));
+    }
+    Ok(())
+}
+
+fn batch(mut operations: Vec<Operation>) -> Operation {
+    if operations.len() == 1 {
+        operations.remove(0)
+    } else {
+        Operation::Batch { operations }
+    }
+}
+
+// structured results for what the operations did to each task
+fn record_operations(operations: &[Operation]) {
+    for operation in operations {
+        match operation {
+            Operation::Add { task } => output::task("added", task),
+            Operation::Edit { after, .. } => output::task("edited", after),
+            Operation::Done { task } => output::task("done", task),
+            Operation::Restore { task } => output::task("restored", task),
+            Operation::Clear { tasks } => tasks.iter().for_each(|task| output::task("cleared", task)),
+            Operation::Batch { operations } => record_operations(operations),
+        }
+    }
+}
+
+// moves the given tasks to the archive and brings recurring ones back with
+// their next due date, returning the journal operations for it
+fn complete_tasks(tasks: &mut Vec<Task>, archive: &mut Vec<Task>, ids: &[u32]) -> io::Result<Vec<Operation>> {
+    let now = Utc::now().timestamp();
+    let mut operations = Vec::new();
+    for id in ids {
+        if let Some(index) = tasks.iter().position(|task| task.id == *id) {
+            let mut removed = tasks.remove(index);
+            removed.completed_at = Some(now);
+            archive.push(removed.clone());
+            let next = match removed.recur {
+                Some(_) => removed.next_occurrence(next_task_id()?, today()),
+                None => None,
+            };
+            operations.push(Operation::Done { task: Box::new(removed) });
+            if let Some(next) = next {
+                tasks.push(next.clone());
+                operations.push(Operation::Add { task: Box::new(next) });
+            }
+        }
+    }
+    Ok(operations)
+}
+
+// ids of the open tasks `task` is still waiting on
+fn open_dependencies(task: &Task, tasks: &[Task]) -> Vec<u32> {
+    task.depends_on.iter().copied().filter(|id| tasks.iter().any(|other| other.id == *id)).collect()
+}
+
+// whether `to` is `from`'s parent, or its parent's parent and so on
+fn descends_from(tasks: &[Task], from: u32, to: u32) -> bool {
+    let mut seen = Vec::new();
+    let mut current = Some(from);
+    while let Some(id) = current {
+        if id == to {
+            return true;
+        }
+        if seen.contains(&id) {
+            return false;
+        }
+        seen.push(id);
+        current = tasks.iter().find(|task| task.id == id).and_then(|task| task.parent);
+    }
+    false
+}
+
+// whether `from` already depends on `to`, directly or through other tasks
+fn depends_on(tasks: &[Task], from: u32, to: u32) -> bool {
+    let mut stack = vec![from];
+    let mut seen = Vec::new();
+    while let Some(id) = stack.pop() {
+        if id == to {
+            return true;
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        if let Some(task) = tasks.iter().find(|task| task.id == id) {
+            stack.extend(task.depends_on.iter().copied());
+        }
+    }
+    false
+}
+
+fn format_ids(ids: &[u32]) -> String {
+    ids.iter().map(|id| format!("#{}", id)).collect::<Vec<String>>().join(", ")
+}
+
+fn mark_done(query: String, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let task = tasks[index].clone();
+        let mut ids = open_descendants(&tasks, task.id);
+        if !ids.is_empty() && !options.assume_yes {
+            let question = format!("\"{}\" has {} open subtask(s). complete them too?", task.description, ids.len());
+            if !interactive() {
+                output::problem("confirmation_required", format!("\"{}\" has {} open subtask(s); rerun with --yes before the command to complete them too", task.description, ids.len()));
+                return Ok(());
+            }
+            if !confirm(&question.yellow().to_string())? {
+                output::problem("cancelled", "cancelled");
+                return Ok(());
+            }
+        }
+        ids.insert(0, task.id);
+        let mut archive = load_archive()?;
+        let operations = complete_tasks(&mut tasks, &mut archive, &ids)?;
+        save_archive(&archive)?;
+        save_tasks(&tasks)?;
+        say!("{}", format!("task done and archived: {}", task.description).green());
+        if ids.len() > 1 {
+            say!("{}", format!("along with {} subtask(s)", ids.len() - 1).green());
+        }
+        for operation in &operations {
+            if let Operation::Add { task: next } = operation {
+                say!("{}", format!("next one: {}", format_task(next)).green());
+            }
+        }
+        let still_blocked = open_dependencies(&task, &tasks);
+        if !still_blocked.is_empty() {
+            say!("{}", format!("note: it was still blocked by {}", format_ids(&still_blocked)).yellow());
+        }
+        for unblocked in tasks.iter().filter(|other| other.depends_on.iter().any(|id| ids.contains(id)) && open_dependencies(other, &tasks).is_empty()) {
+            output::task("unblocked", unblocked);
+            say!("{}", format!("unblocked: {}", format_task(unblocked)).yellow());
+        }
+        record_operations(&operations);
+        journal::record(batch(operations))?;
+    }
+    Ok(())
+}
+
+// completes every open task the filter matches, after showing them
+fn mark_done_matching(filter: &Filter, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    let ids: Vec<u32> = filtered_tasks(filter, options.project.as_deref(), false)?.iter()
+        .filter(|task| task.completed_at.is_none())
+        .map(|task| task.id)
+        .collect();
+    if ids.is_empty() {
+        say!("{}", "no tasks found".red());
+        return Ok(());
+    }
+    for task in tasks.iter().filter(|task| ids.contains(&task.id)) {
+        print_task(task);
+    }
+    if !options.assume_yes {
+        if !interactive() {
+            output::problem("confirmation_required", format!("{} task(s) match; rerun with --yes before the command to complete them", ids.len()));
+            return Ok(());
+        }
+        if !confirm(&format!("complete these {} task(s)?", ids.len()).yellow().to_string())? {
+            output::problem("cancelled", "cancelled");
+            return Ok(());
+        }
+    }
+    let mut archive = load_archive()?;
+    let operations = complete_tasks(&mut tasks, &mut archive, &ids)?;
+    save_archive(&archive)?;
+    save_tasks(&tasks)?;
+    say!("{}", format!("{} task(s) done and archived", ids.len()).green());
+    record_operations(&operations);
+    journal::record(batch(operations))?;
+    Ok(())
+}
+
+fn undo_last(count: usize) -> io::Result<()> {
+    let mut journal = journal::load_journal()?;
+    if journal.undo.is_empty() {
+        output::problem("nothing_to_undo", "no undo available");
+        return Ok(());
+    }
+    let mut tasks = load_tasks()?;
+    let mut archive = load_archive()?;
+    let mut undone = 0;
+    while undone < count {
+        let Some(entry) = journal.undo.pop() else { break };
+        if let Err(e) = entry.operation.revert(&mut tasks, &mut archive) {
+            journal.undo.push(entry);
+            output::error("journal_out_of_sync", format!("stopped undoing: {}", e));
+            break;
+        }
+        output::record("undone", json!({ "description": entry.operation.describe(), "operation": entry.operation }));
+        say!("{}", format!("undone {}", entry.operation.describe()).green());
+        journal.redo.push(entry);
+        undone += 1;
+    }
+    save_tasks(&tasks)?;
+    save_archive(&archive)?;
+    journal::save_journal(&journal)?;
+    say!("{}", format!("undo successful: {} operation(s) reverted", undone).green());
+    Ok(())
+}
+
+fn redo_last(count: usize) -> io::Result<()> {
+    let mut journal = journal::load_journal()?;
+    if journal.redo.is_empty() {
+        output::problem("nothing_to_redo", "no redo available");
+        return Ok(());
+    }
+    let mut tasks = load_tasks()?;
+    let mut archive = load_archive()?;
+    let mut redone = 0;
+    while redone < count {
+        let Some(entry) = journal.redo.pop() else { break };
+        if let Err(e) = entry.operation.apply(&mut tasks, &mut archive) {
+            journal.redo.push(entry);
+            output::error("journal_out_of_sync", format!("stopped redoing: {}", e));
+            break;
+        }
+        output::record("redone", json!({ "description": entry.operation.describe(), "operation": entry.operation }));
+        say!("{}", format!("redone {}", entry.operation.describe()).green());
+        journal.undo.push(entry);
+        redone += 1;
+    }
+    save_tasks(&tasks)?;
+    save_archive(&archive)?;
+    journal::save_journal(&journal)?;
+    say!("{}", format!("redo successful: {} operation(s) reapplied", redone).green());
+    Ok(())
+}
+
+fn show_history(count: usize) -> io::Result<()> {
+    let journal = journal::load_journal()?;
+    if journal.undo.is_empty() && journal.redo.is_empty() {
+        say!("{}", "no history yet".red());
+        return Ok(());
+    }
+    for entry in journal.redo.iter().take(count) {
+        output::record("operation", json!({ "at": entry.at, "undone": true, "description": entry.operation.describe() }));
+        say!("{}", format!("   {} (undone) {}", format_timestamp(entry.at), entry.operation.describe()).dimmed());
+    }
+    for (i, entry) in journal.undo.iter().rev().take(count).enumerate() {
+        output::record("operation", json!({ "at": entry.at, "undone": false, "description": entry.operation.describe() }));
+        say!("{}", format!("{:>2} {} {}", i + 1, format_timestamp(entry.at), entry.operation.describe()).cyan());
+    }
+    Ok(())
+}
+
+fn print_archived_task(task: &Task) {
+    output::task("task", task);
+    let completed = task.completed_at.map(format_timestamp).unwrap_or_else(|| "unknown".to_string());
+    say!("{}", format!("{} (done {})", format_task(task), completed).cyan());
+}
+
+fn list_archive() -> io::Result<()> {
+    let mut archive = load_archive()?;
+    archive.sort_by_key(|task| task.completed_at);
+    if archive.is_empty() {
+        say!("{}", "archive is empty".red());
+    } else {
+        for task in &archive {
+            print_archived_task(task);
+        }
+    }
+    Ok(())
+}
+
+fn search_archive(query: String) -> io::Result<()> {
+    let archive = load_archive()?;
+    let query_lower = query.to_lowercase();
+    let filtered: Vec<&Task> = archive.iter().filter(|task| task.description.to_lowercase().contains(&query_lower)).collect();
+    if filtered.is_empty() {
+        say!("{}", format!("no archived tasks found matching \"{}\"", query).red());
+    } else {
+        for task in filtered {
+            print_archived_task(task);
+        }
+    }
+    Ok(())
+}
+
+fn restore_task(query: String, options: &MatchOptions) -> io::Result<()> {
+    let mut archive = load_archive()?;
+    if let Some(index) = select_task(&archive, &query, options)? {
+        let archived = archive.remove(index);
+        let mut restored = archived.clone();
+        restored.completed_at = None;
+        let mut tasks = load_tasks()?;
+        tasks.push(restored.clone());
+        save_tasks(&tasks)?;
+        save_archive(&archive)?;
+        output::task("restored", &restored);
+        say!("{}", format!("task restored: {}", restored.description).green());
+        journal::record(Operation::Restore { task: Box::new(archived) })?;
+    }
+    Ok(())
+}
+
+fn edit_task(query: String, new_description: String, attributes: Attributes, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        let task = &mut tasks[index];
+        // `edit x /// due:friday` only changes attributes and keeps the description
+        if !new_description.is_empty() {
+            task.description = new_description;
+        }
+        task.due = attributes.due.or(task.due);
+        task.priority = attributes.priority.or(task.priority);
+        task.recur = attributes.recur.or(task.recur);
+        for tag in &attributes.tags {
+            task.add_tag(tag);
+        }
+        let after = save_edit(&mut tasks, index, before)?;
+        say!("{}", format!("task updated to: {}", after.description).green());
+    }
+    Ok(())
+}
+
+// splits `<task> <value>` into the task query and the value, which is the longest
+// trailing run of words `parse` accepts; one of the `clear` words gives no value
+fn split_trailing_value<T>(words: &[String], clear: &[&str], parse: impl Fn(&str) -> Option<T>) -> Option<(String, Option<T>)> {
+    let last = words.last()?.to_lowercase();
+    if clear.contains(&last.as_str()) {
+        return Some((words[..words.len() - 1].join(" "), None));
+    }
+    (1..words.len()).find_map(|split| parse(&words[split..].join(" ")).map(|value| (words[..split].join(" "), Some(value))))
+}
+
+// `words` holds the query followed by the date, e.g. "rap song next monday"
+fn set_due(words: &[String], options: &MatchOptions) -> io::Result<()> {
+    let Some((query, due)) = split_trailing_value(words, &["none", "clear"], |text| dates::parse_date(text, today())) else {
+        output::problem("invalid_input", "please end the command with a date, e.g. taskz due <task> next friday");
+        return Ok(());
+    };
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        tasks[index].due = due;
+        let after = save_edit(&mut tasks, index, before)?;
+        match due {
+            Some(due) => say!("{}", format!("{} is now due {}", after.description, dates::format_date(due)).green()),
+            None => say!("{}", format!("due date removed from {}", after.description).green()),
+        }
+    }
+    Ok(())
+}
+
+fn set_priority(query: String, priority: Option<Priority>, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        tasks[index].priority = priority;
+        let after = save_edit(&mut tasks, index, before)?;
+        match priority {
+            Some(priority) => say!("{}", format!("{} now has priority {}", after.description, priority.letter()).green()),
+            None => say!("{}", format!("priority removed from {}", after.description).green()),
+        }
+    }
+    Ok(())
+}
+
+fn tag_task(query: String, tag: String, remove: bool, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        let changed = if remove {
+            let count = tasks[index].tags.len();
+            tasks[index].tags.retain(|existing| *existing != tag);
+            count != tasks[index].tags.len()
+        } else {
+            tasks[index].add_tag(&tag)
+        };
+        if !changed {
+            let state = if remove { "is not tagged" } else { "is already tagged" };
+            output::problem("unchanged", format!("{} {} +{}", before.description, state, tag));
+            return Ok(());
+        }
+        let after = save_edit(&mut tasks, index, before)?;
+        if remove {
+            say!("{}", format!("removed +{} from {}", tag, after.description).green());
+        } else {
+            say!("{}", format!("tagged {} with +{}", after.description, tag).green());
+        }
+    }
+    Ok(())
+}
+
+fn list_tags() -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for tag in tasks.iter().flat_map(|task| task.tags.iter()) {
+        match counts.iter_mut().find(|(name, _)| name == tag) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((tag.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    if counts.is_empty() {
+        say!("{}", "no tags found".red());
+    } else {
+        for (tag, count) in counts {
+            output::record("tag", json!({ "name": tag, "count": count }));
+            say!("{}", format!("{:>4} +{}", count, tag).cyan());
+        }
+    }
+    Ok(())
+}
+
+fn move_task(query: String, project: Option<String>, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        if tasks[index].project == project {
+            let place = project.map(|project| format!("is already in {}", project)).unwrap_or_else(|| "has no project".to_string());
+            output::problem("unchanged", format!("{} {}", tasks[index].description, place));
+            return Ok(());
+        }
+        let before = tasks[index].clone();
+        tasks[index].project = project.clone();
+        let after = save_edit(&mut tasks, index, before)?;
+        match project {
+            Some(project) => say!("{}", format!("moved {} to {}", after.description, project).green()),
+            None => say!("{}", format!("removed {} from its project", after.description).green()),
+        }
+    }
+    Ok(())
+}
+
+fn list_projects() -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let mut counts: Vec<(Option<String>, usize)> = Vec::new();
+    for task in &tasks {
+        match counts.iter_mut().find(|(name, _)| *name == task.project) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((task.project.clone(), 1)),
+        }
+    }
+    counts.sort();
+    if counts.is_empty() {
+        say!("{}", "no tasks found".red());
+    } else {
+        for (project, count) in counts {
+            output::record("project", json!({ "name": project, "count": count }));
+            say!("{}", format!("{:>4} {}", count, project.as_deref().unwrap_or("(no project)")).cyan());
+        }
+    }
+    Ok(())
+}
+
+fn block_task(query: String, dependency_query: String, remove: bool, options: &MatchOptions) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    let Some(index) = select_task(&tasks, &query, options)? else {
+        return Ok(());
+    };
+    let Some(dependency_index) = select_task(&tasks, &dependency_query, options)? else {
+        return Ok(());
+    };
+    let (id, dependency) = (tasks[index].id, tasks[dependency_index].clone());
+    if id == dependency.id {
+        output::problem("invalid_dependency", "a task can't depend on itself");
+        return Ok(());
+    }
+    let before = tasks[index].clone();
+    if remove {
+        if !before.depends_on.contains(&dependency.id) {
+            output::problem("unchanged", format!("{} does not depend on {}", before.description, dependency.description));
+            return Ok(());
+        }
+        tasks[index].depends_on.retain(|other| *other != dependency.id);
+    } else {
+        if before.depends_on.contains(&dependency.id) {
+            output::problem("unchanged", format!("{} already depends on {}", before.description, dependency.description));
+            return Ok(());
+        }
+        let mut all_tasks = tasks.clone();
+        all_tasks.extend(load_archive()?);
+        if depends_on(&all_tasks, dependency.id, id) {
+            output::problem("dependency_cycle", format!("can't do that: {} already depends on {}, that would be a cycle", dependency.description, before.description));
+            return Ok(());
+        }
+        tasks[index].depends_on.push(dependency.id);
+    }
+    let after = save_edit(&mut tasks, index, before)?;
+    if remove {
+        say!("{}", format!("{} no longer depends on {}", after.description, dependency.description).green());
+    } else {
+        say!("{}", format!("{} is now blocked by {}", after.description, dependency.description).green());
+    }
+    Ok(())
+}
+
+// `ready` lists tasks with nothing left to wait on, `blocked` the rest
+fn list_by_dependencies(blocked: bool, project: Option<&str>) -> io::Result<()> {
+    let mut tasks = load_tasks()?;
+    tasks.sort_by_key(|task| (task.created_at, task.id));
+    let mut shown = 0;
+    for task in tasks.iter().filter(|task| project.is_none_or(|project| task.project.as_deref() == Some(project))) {
+        let waiting_on = open_dependencies(task, &tasks);
+        if waiting_on.is_empty() == blocked {
+            continue;
+        }
+        output::record("task", json!({ "task": task, "blocked_by": waiting_on }));
+        if blocked {
+            say!("{}{}", render_task(task, true), format!(" blocked by {}", format_ids(&waiting_on)).dimmed());
+        } else {
+            print_task(task);
+        }
+        shown += 1;
+    }
+    if shown == 0 {
+        say!("{}", if blocked { "no blocked tasks" } else { "no tasks ready" }.red());
+    }
+    Ok(())
+}
+
+// `words` holds the query followed by the rule, e.g. "weekly report every 2 weeks"
+fn set_recurrence(words: &[String], options: &MatchOptions) -> io::Result<()> {
+    let Some((query, recur)) = split_trailing_value(words, &["none", "never"], Recurrence::parse) else {
+        output::problem("invalid_input", "please end the command with a rule, e.g. taskz recur <task> every 2 weeks");
+        return Ok(());
+    };
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        tasks[index].recur = recur;
+        let after = save_edit(&mut tasks, index, before)?;
+        match recur {
+            Some(recur) => say!("{}", format!("{} now recurs {}", after.description, recur).green()),
+            None => say!("{}", format!("{} no longer recurs", after.description).green()),
+        }
+    }
+    Ok(())
+}
+
+// a new file in the temp directory that nobody else can have prepared: the name
+// has a random part and an existing file or symlink with it is never opened
+fn create_note_file(initial: &str, id: u32) -> io::Result<PathBuf> {
+    let mut attempt = 0;
+    loop {
+        // std seeds its hash keys from the OS, which makes them a cheap random number
+        let suffix = RandomState::new().hash_one((attempt, Utc::now().timestamp_nanos_opt()));
+        let path = env::temp_dir().join(format!("taskz-note-{}-{}-{:x}.md", id, std::process::id(), suffix));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(initial.as_bytes())?;
+                return Ok(path);
+            },
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// opens $VISUAL or $EDITOR on a temporary file holding `initial`
+fn edit_in_editor(initial: &str, id: u32) -> io::Result<String> {
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| {
+        if cfg!(target_os = "windows") { "notepad".to_string() } else { "vi".to_string() }
+    });
+    let path = create_note_file(initial, id)?;
+    // editors like "code --wait" come with their own arguments
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&path).status()?;
+    let edited = fs::read_to_string(&path);
+    fs::remove_file(&path)?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", program, status)));
+    }
+    edited
+}
+
+// todo.txt lines have nowhere to keep notes or annotations
+fn notes_unsupported() -> bool {
+    if todo_txt_path().is_none() {
+        return false;
+    }
+    output::problem("unsupported", "notes and annotations can't be stored in todo.txt, unset TASKZ_TODO_TXT to use them");
+    true
+}
+
+fn edit_notes(query: String, options: &MatchOptions) -> io::Result<()> {
+    if notes_unsupported() {
+        return Ok(());
+    }
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        // piped input replaces the notes, otherwise they are opened in an editor
+        let notes = if io::stdin().is_terminal() {
+            let initial = before.notes.as_ref().map(|notes| format!("{}\n", notes)).unwrap_or_default();
+            edit_in_editor(&initial, before.id)?
+        } else {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        };
+        let notes = notes.trim_end();
+        tasks[index].notes = (!notes.is_empty()).then(|| notes.to_string());
+        if tasks[index].notes == before.notes {
+            say!("{}", "notes unchanged".yellow());
+            return Ok(());
+        }
+        let after = save_edit(&mut tasks, index, before)?;
+        match after.notes {
+            Some(_) => say!("{}", format!("notes saved for {}", after.description).green()),
+            None => say!("{}", format!("notes removed from {}", after.description).green()),
+        }
+    }
+    Ok(())
+}
+
+fn annotate_task(query: String, text: String, options: &MatchOptions) -> io::Result<()> {
+    if notes_unsupported() {
+        return Ok(());
+    }
+    let mut tasks = load_tasks()?;
+    if let Some(index) = select_task(&tasks, &query, options)? {
+        let before = tasks[index].clone();
+        tasks[index].annotations.push(Annotation { at: Utc::now().timestamp(), text });
+        let after = save_edit(&mut tasks, index, before)?;
+        say!("{}", format!("annotation added to {}", after.description).green());
+    }
+    Ok(())
+}
+
+fn describe_id(id: u32, tasks: &[Task], archive: &[Task]) -> String {
+    match tasks.iter().find(|task| task.id == id) {
+        Some(task) => format_task(task),
+        None => match archive.iter().find(|task| task.id == id) {
+            Some(task) => format!("{} (done)", format_task(task)),
+            None => format!("#{} (deleted)", id),
+        },
+    }
+}
+
+// ids look in the archive too; `--archived` fuzzy matches completed tasks instead
+fn show_task(query: String, archived: bool, options: &MatchOptions) -> io::Result<()> {
+    let tasks = load_tasks()?;
+    let archive = load_archive()?;
+    let by_id = parse_task_id(&query).and_then(|id| {
+        tasks.iter().chain(archive.iter()).find(|task| task.id == id)
+    });
+    let task = match by_id {
+        Some(task) => task,
+        None => {
+            let candidates = if archived { &archive } else { &tasks };
+            match select_task(candidates, &query, options)? {
+                Some(index) => &candidates[index],
+                None => return Ok(()),
+            }
+        }
+    };
+    let field = |name: &str, value: String| say!("{} {}", format!("{:>12}", name).dimmed(), value);
+    say!("{}", render_task(task, true));
+    field("id", format!("#{}", task.id));
+    field("description", task.description.clone());
+    field("status", if task.completed_at.is_some() { "done".to_string() } else { "open".to_string() });
+    field("created", format_timestamp(task.created_at));
+    if let Some(updated_at) = task.updated_at {
+        field("updated", format_timestamp(updated_at));
+    }
+    if let Some(completed_at) = task.completed_at {
+        field("completed", format_timestamp(completed_at));
+    }
+    if let Some(due) = task.due {
+        field("due", dates::format_date(due));
+    }
+    if let Some(priority) = task.priority {
+        field("priority", format!("{} (urgency {:.1})", priority.letter(), task.urgency(today())));
+    }
+    if let Some(recur) = task.recur {
+        field("recurs", recur.to_string());
+    }
+    if !task.tags.is_empty() {
+        field("tags", format_tags(task, false).trim().to_string());
+    }
+    if let Some(project) = &task.project {
+        field("project", project.clone());
+    }
+    if let Some(parent) = task.parent {
+        field("subtask of", describe_id(parent, &tasks, &archive));
+    }
+    if let Some(progress) = subtask_progress(task, &tasks, &archive) {
+        field("progress", progress.trim().to_string());
+    }
+    let subtasks: Vec<u32> = tasks.iter().chain(archive.iter()).filter(|child| child.parent == Some(task.id)).map(|child| child.id).collect();
+    for (i, id) in subtasks.iter().enumerate() {
+        field(if i == 0 { "subtasks" } else { "" }, describe_id(*id, &tasks, &archive));
+    }
+    for (i, id) in task.depends_on.iter().enumerate() {
+        field(if i == 0 { "depends on" } else { "" }, describe_id(*id, &tasks, &archive));
+    }
+    if let Some(notes) = &task.notes {
+        for (i, line) in notes.lines().enumerate() {
+            field(if i == 0 { "notes" } else { "" }, line.to_string());
+        }
+    }
+    for (i, annotation) in task.annotations.iter().enumerate() {
+        field(if i == 0 { "annotations" } else { "" }, format!("{} {}", format_timestamp(annotation.at).dimmed(), annotation.text));
+    }
+    let journal = journal::load_journal()?;
+    let history = journal::task_history(&journal, task);
+    for (i, (at, event)) in history.iter().enumerate() {
+        field(if i == 0 { "history" } else { "" }, format!("{} {}", format_timestamp(*at).dimmed(), event));
+    }
+    // the history comes from the undo journal, which is shared by all tasks
+    let limit = format!("only the last {} changes to any task that were not undone are kept", journal::MAX_JOURNAL_ENTRIES);
+    field(if history.is_empty() { "history" } else { "" }, limit.dimmed().to_string());
+    output::record("task", json!({
+        "task": task,
+        "subtasks": subtasks,
+        "history": history.iter().map(|(at, event)| json!({ "at": at, "event": event })).collect::<Vec<_>>(),
+        "history_limit": journal::MAX_JOURNAL_ENTRIES,
+    }));
+    Ok(())
+}
+
+// writes the matching tasks in `format` to `path`, or to stdout without one
+fn export_tasks(format: Format, group: Option<Group>, filter: Filter, all: bool, path: Option<String>, project: Option<&str>) -> io::Result<()> {
+    // `--all` takes completed tasks along whatever the filter says about status
+    let filter = if all {
+        let any_status = Filter::Or(vec![Filter::Status(filter::Status::Open), Filter::Status(filter::Status::Done)]);
+        Filter::And(vec![filter, any_status])
+    } else {
+        filter
+    };
+    let mut tasks = filtered_tasks(&filter, project, false)?;
+    tasks.sort_by_key(|task| task.id);
+    let text = match group {
+        Some(group) => markdown::write(&tasks, Some(group)),
+        None => formats::write(format, &tasks).map_err(io::Error::other)?,
+    };
+    match path {
+        Some(path) => {
+            fs::write(&path, &text)?;
+            output::record("exported", json!({ "format": format.name(), "count": tasks.len(), "path": path }));
+            say!("{}", format!("exported {} task(s) to {}", tasks.len(), path).green());
+        },
+        None if output::is_human() => print!("{}", text),
+        None => output::record("exported", json!({ "format": format.name(), "count": tasks.len(), "content": text })),
+    }
+    Ok(())
+}
+
+fn describe_import(task: &Task) -> String {
+    let done = if task.completed_at.is_some() { " (done)" } else { "" };
+    format!("{}{}{}", format_task_body(task), format_tags(task, true), done)
+}
+
+// adds the tasks in `path` under new ids, skipping ones that are already in the
+// list or archive (same description, due date and done or not)
+fn import_tasks(path: String, format: Format, dry_run: bool, project: Option<&str>) -> io::Result<()> {
+    let text = fs::read_to_string(&path)?;
+    let parsed = match formats::read(format, &text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            output::error("invalid_input", format!("could not import {}: {}", path, e));
+            return Ok(());
+        }
+    };
+    let mut warnings = parsed.warnings;
+    let skipped = parsed.skipped;
+    let mut tasks = load_tasks()?;
+    let mut archive = load_archive()?;
+    let mut imported: Vec<Task> = Vec::new();
+    // ids in the file paired with the ids those tasks have here
+    let mut ids: Vec<(u32, u32)> = Vec::new();
+    let mut duplicates = 0;
+    let first_free = tasks.iter().chain(&archive).map(|task| task.id).max().unwrap_or(0) + 1;
+    for mut task in parsed.tasks {
+        if task.project.is_none() {
+            task.project = project.map(str::to_string);
+        }
+        let description = task.description.trim().to_lowercase();
+        let done = task.completed_at.is_some();
+        let same = |other: &&Task| other.description.trim().to_lowercase() == description && other.due == task.due && other.completed_at.is_some() == done;
+        // open rows are checked against the list and completed ones against the archive;
+        // rows with their own id are distinct from the rest of the file
+        let existing = if done { archive.iter().find(same) } else { tasks.iter().find(same) }
+            .or_else(|| imported.iter().filter(|_| task.id == 0).find(same));
+        if let Some(existing) = existing {
+            if task.id != 0 {
+                ids.push((task.id, existing.id));
+            }
+            duplicates += 1;
+            output::task("duplicate", &task);
+            say!("{}", format!("duplicate, skipped: {}", describe_import(&task)).dimmed());
+            continue;
+        }
+        // a dry run numbers the rows after the existing tasks, only to link them up
+        let id = if dry_run { first_free + imported.len() as u32 } else { next_task_id()? };
+        if task.id != 0 {
+            ids.push((task.id, id));
+        }
+        task.id = id;
+        imported.push(task);
+    }
+    // parents and dependencies point at ids from the file until they are mapped
+    let lookup = |id: u32| ids.iter().find(|(old, _)| *old == id).map(|(_, new)| *new);
+    for task in imported.iter_mut() {
+        if let Some(parent) = task.parent {
+            task.parent = lookup(parent);
+            if task.parent.is_none() {
+                warnings.push(format!("parent #{} of \"{}\" is not in the file, dropped", parent, task.description));
+            }
+        }
+        let depends_on = task.depends_on.clone();
+        task.depends_on = depends_on.iter().filter_map(|id| lookup(*id)).collect();
+        if task.depends_on.len() < depends_on.len() {
+            warnings.push(format!("some dependencies of \"{}\" are not in the file, dropped", task.description));
+        }
+    }
+    // links are added back one at a time, so the one that would close a cycle is the one dropped
+    let mut linked: Vec<Task> = tasks.iter().chain(&archive).cloned().collect();
+    for task in imported.iter_mut() {
+        let parent = task.parent.take();
+        let dependencies = std::mem::take(&mut task.depends_on);
+        linked.push(task.clone());
+        let index = linked.len() - 1;
+        if let Some(parent) = parent {
+            if descends_from(&linked, parent, task.id) {
+                warnings.push(format!("\"{}\" would be its own ancestor, its parent was dropped", task.description));
+            } else {
+                task.parent = Some(parent);
+                linked[index].parent = Some(parent);
+            }
+        }
+        for dependency in dependencies {
+            if depends_on(&linked, dependency, task.id) {
+                warnings.push(format!("\"{}\" depending on #{} would be a cycle, dropped", task.description, dependency));
+            } else if !task.depends_on.contains(&dependency) {
+                task.depends_on.push(dependency);
+                linked[index].depends_on.push(dependency);
+            }
+        }
+    }
+    if dry_run {
+        for task in imported.iter_mut() {
+            task.id = 0;
+        }
+    }
+    for warning in &warnings {
+        output::record("warning", json!({ "message": warning }));
+        say!("{}", format!("warning: {}", warning).yellow());
+    }
+    let completed = imported.iter().filter(|task| task.completed_at.is_some()).count();
+    let summary = json!({ "imported": imported.len(), "completed": completed, "duplicates": duplicates, "skipped": skipped, "warnings": warnings.len(), "dry_run": dry_run });
+    // entries the reader left out, and how much was only partly carried over
+    let mut rest = String::new();
+    if skipped > 0 {
+        rest.push_str(&format!(" and {} other(s)", skipped));
+    }
+    if !warnings.is_empty() {
+        rest.push_str(&format!(", {} warning(s) above", warnings.len()));
+    }
+    if dry_run {
+        for task in &imported {
+            output::task("imported", task);
+            say!("{}", format!("would import: {}", describe_import(task)).cyan());
+        }
+        output::record("summary", summary);
+        say!("{}", format!("dry run: would import {} task(s), skip {} duplicate(s){}", imported.len(), duplicates, rest).yellow());
+        return Ok(());
+    }
+    let mut operations = Vec::new();
+    for task in &imported {
+        output::task("imported", task);
+        say!("{}", format!("imported: {}", format_task(task)).cyan());
+        if task.completed_at.is_some() {
+            // journaled as added and then completed, so undo can take it back out of the archive
+            let mut open = task.clone();
+            open.completed_at = None;
+            operations.push(Operation::Add { task: Box::new(open) });
+            operations.push(Operation::Done { task: Box::new(task.clone()) });
+            archive.push(task.clone());
+        } else {
+            operations.push(Operation::Add { task: Box::new(task.clone()) });
+            tasks.push(task.clone());
+        }
+    }
+    save_tasks(&tasks)?;
+    save_archive(&archive)?;
+    if !operations.is_empty() {
+        journal::record(batch(operations))?;
+    }
+    output::record("summary", summary);
+    say!("{}", format!("imported {} task(s) ({} completed), skipped {} duplicate(s){}", imported.len(), completed, duplicates, rest).green());
+    Ok(())
+}
+
+// what a markdown file held after the last sync-md, so a difference can be
+// told apart as a change in the file or in taskz
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SyncedItem {
+    id: u32,
+    done: bool,
+    text: String,
+}
+
+fn load_sync_state() -> io::Result<BTreeMap<String, Vec<SyncedItem>>> {
+    let path = get_data_file_path("md_sync.json")?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data).unwrap_or_default())
+}
+
+fn save_sync_state(state: &BTreeMap<String, Vec<SyncedItem>>) -> io::Result<()> {
+    let data = serde_json::to_string_pretty(state)?;
+    fs::write(get_data_file_path("md_sync.json")?, data)
+}
+
+// brings a task in line with the checklist item it is linked to, returning
+// the task as it ended up
+fn apply_item(item: &markdown::Item, id: u32, tasks: &mut Vec<Task>, archive: &mut Vec<Task>, operations: &mut Vec<Operation>, changes: &mut Vec<(&'static str, &'static str, Task)>, warnings: &mut Vec<String>) -> io::Result<Option<Task>> {
+    let mut warn = |message: String| warnings.push(format!("line {}: {}", item.line + 1, message));
+    let parsed = markdown::task_for(item, &mut warn);
+    if let Some(index) = archive.iter().position(|task| task.id == id) {
+        if item.done {
+            warn("completed tasks can't be edited, kept the taskz version".to_string());
+            return Ok(Some(archive[index].clone()));
+        }
+        let archived = archive.remove(index);
+        let mut restored = archived.clone();
+        restored.completed_at = None;
+        tasks.push(restored.clone());
+        operations.push(Operation::Restore { task: Box::new(archived) });
+        changes.push(("taskz", "reopened", restored));
+    }
+    let Some(index) = tasks.iter().position(|task| task.id == id) else {
+        return Ok(None);
+    };
+    let before = tasks[index].clone();
+    let task = &mut tasks[index];
+    task.description = parsed.description;
+    task.due = parsed.due;
+    task.priority = parsed.priority;
+    task.tags = parsed.tags;
+    task.project = parsed.project;
+    task.recur = parsed.recur;
+    if markdown::item_text(task) != markdown::item_text(&before) {
+        task.updated_at = Some(Utc::now().timestamp());
+        operations.push(Operation::Edit { before: Box::new(before), after: Box::new(task.clone()) });
+        changes.push(("taskz", "edited", task.clone()));
+    }
+    if !item.done {
+        return Ok(Some(tasks[index].clone()));
+    }
+    operations.extend(complete_tasks(tasks, archive, &[id])?);
+    let done = archive.iter().find(|task| task.id == id).cloned();
+    changes.extend(done.iter().map(|task| ("taskz", "completed", task.clone())));
+    Ok(done)
+}
+
+// two-way sync between a markdown checklist and the task list: new items,
+// edits and completions on either side are carried over to the other
+fn sync_markdown(path: String, project: Option<&str>, dry_run: bool) -> io::Result<()> {
+    let text = if PathBuf::from(&path).exists() { fs::read_to_string(&path)? } else { String::new() };
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut items = markdown::items(&text);
+    let key = std::path::absolute(&path)?.to_string_lossy().into_owned();
+    let mut state = load_sync_state()?;
+    let last = state.get(&key).cloned().unwrap_or_default();
+    let mut tasks = load_tasks()?;
+    let mut archive = load_archive()?;
+    let mut operations = Vec::new();
+    let mut warnings = Vec::new();
+    // (where the change went, what it was, the task)
+    let mut changes: Vec<(&'static str, &'static str, Task)> = Vec::new();
+    let file_ids: Vec<u32> = items.iter().filter_map(|item| item.id).collect();
+    let mut seen: Vec<u32> = Vec::new();
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<u32> = Vec::new();
+    let mut linked: Vec<u32> = Vec::new();
+    // first link every item to a task: by its id, by its description, or as a new one
+    for item in items.iter_mut() {
+        if let Some(id) = item.id.filter(|id| !seen.contains(id)) {
+            let unchanged = last.iter().any(|synced| synced.id == id && synced.done == item.done && synced.text == item.text);
+            if tasks.iter().chain(archive.iter()).any(|task| task.id == id) {
+                seen.push(id);
+                continue;
+            }
+            if unchanged {
+                // deleted in taskz since the last sync
+                removed.push(item.line);
+                let mut task = markdown::task_for(item, &mut |_| {});
+                task.id = id;
+                changes.push(("file", "removed", task));
+                continue;
+            }
+        }
+        item.id = None;
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", item.line + 1, message));
+        let mut task = markdown::task_for(item, &mut warn);
+        if task.project.is_none() {
+            task.project = project.map(str::to_string);
+        }
+        let description = task.description.to_lowercase();
+        let existing = tasks.iter().chain(archive.iter())
+            .find(|other| !seen.contains(&other.id) && !file_ids.contains(&other.id) && other.description.to_lowercase() == description);
+        if let Some(existing) = existing {
+            // the line gets the task's id below, so later edits on either side still find it
+            item.id = Some(existing.id);
+            seen.push(existing.id);
+            linked.push(existing.id);
+            changes.push(("file", "linked", existing.clone()));
+            continue;
+        }
+        task.id = if dry_run { 0 } else { next_task_id()? };
+        if task.completed_at.is_some() {
+            let mut open = task.clone();
+            open.completed_at = None;
+            operations.push(Operation::Add { task: Box::new(open) });
+            operations.push(Operation::Done { task: Box::new(task.clone()) });
+            archive.push(task.clone());
+        } else {
+            operations.push(Operation::Add { task: Box::new(task.clone()) });
+            tasks.push(task.clone());
+        }
+        lines[item.line] = markdown::item_line(&item.prefix, &task);
+        item.id = Some(task.id);
+        seen.push(task.id);
+        added.push(task.id);
+        changes.push(("taskz", "added", task));
+    }
+    // then settle linked items that differ, by whichever side changed since the last sync
+    for item in &items {
+        let Some(id) = item.id.filter(|id| !added.contains(id) && !removed.contains(&item.line)) else {
+            continue;
+        };
+        let Some(task) = tasks.iter().chain(archive.iter()).find(|task| task.id == id).cloned() else {
+            continue;
+        };
+        let done = task.completed_at.is_some();
+        let rendered = markdown::item_text(&task);
+        if item.done == done && item.text == rendered {
+            if linked.contains(&id) {
+                lines[item.line] = markdown::item_line(&item.prefix, &task);
+            }
+            continue;
+        }
+        let previous = last.iter().find(|synced| synced.id == id);
+        let file_changed = previous.is_none_or(|synced| synced.done != item.done || synced.text != item.text);
+        let taskz_changed = previous.is_some_and(|synced| synced.done != done || synced.text != rendered);
+        let task = if file_changed && !taskz_changed {
+            match apply_item(item, id, &mut tasks, &mut archive, &mut operations, &mut changes, &mut warnings)? {
+                Some(task) => task,
+                None => continue,
+            }
+        } else {
+            if file_changed {
+                warnings.push(format!("line {}: changed in both places, kept the taskz version", item.line + 1));
+            }
+            let change = match (done, item.done) {
+                (true, false) => "completed",
+                (false, true) => "reopened",
+                _ => "updated",
+            };
+            changes.push(("file", change, task.clone()));
+            task
+        };
+        lines[item.line] = markdown::item_line(&item.prefix, &task);
+    }
+    // open tasks missing from the file were either added in taskz or deleted from the file
+    let mut deleted = Vec::new();
+    for task in tasks.iter().filter(|task| project.is_none_or(|project| task.project.as_deref() == Some(project))) {
+        if seen.contains(&task.id) {
+            continue;
+        }
+        if last.iter().any(|synced| synced.id == task.id) {
+            deleted.push(task.clone());
+            changes.push(("taskz", "deleted", task.clone()));
+        } else {
+            lines.push(markdown::item_line("- ", task));
+            changes.push(("file", "added", task.clone()));
+        }
+    }
+    if !deleted.is_empty() {
+        tasks.retain(|task| !deleted.iter().any(|other| other.id == task.id));
+        operations.push(Operation::Clear { tasks: deleted });
+    }
+    let lines: Vec<String> = lines.into_iter().enumerate().filter(|(i, _)| !removed.contains(i)).map(|(_, line)| line).collect();
+    let synced = if lines.is_empty() { String::new() } else { format!("{}\n", lines.join("\n")) };
+
+    for (to, change, task) in &changes {
+        output::record("synced", json!({ "to": to, "change": change, "task": task }));
+        let arrow = if *to == "taskz" { "file -> taskz" } else { "taskz -> file" };
+        // tasks a dry run would add have no id yet
+        let shown = if task.id == 0 { describe_import(task) } else { format_task(task) };
+        say!("{} {}", format!("{} {}:", arrow, change).cyan(), shown);
+    }
+    for warning in &warnings {
+        output::record("warning", json!({ "message": warning }));
+        say!("{}", format!("warning: {}", warning).yellow());
+    }
+    let to_taskz = changes.iter().filter(|(to, _, _)| *to == "taskz").count();
+    let to_file = changes.len() - to_taskz;
+    output::record("summary", json!({ "to_taskz": to_taskz, "to_file": to_file, "warnings": warnings.len(), "dry_run": dry_run }));
+    if dry_run {
+        say!("{}", format!("dry run: would make {} change(s) in taskz and {} in {}", to_taskz, to_file, path).yellow());
+        return Ok(());
+    }
+    if synced != text {
+        fs::write(&path, &synced)?;
+    }
+    if !operations.is_empty() {
+        save_tasks(&tasks)?;
+        save_archive(&archive)?;
+        journal::record(batch(operations))?;
+    }
+    let snapshot = markdown::items(&synced).into_iter()
+        .filter_map(|item| Some(SyncedItem { id: item.id?, done: item.done, text: item.text }))
+        .collect();
+    state.insert(key, snapshot);
+    save_sync_state(&state)?;
+    match changes.len() {
+        0 => say!("{}", format!("{} is already in sync", path).green()),
+        _ => say!("{}", format!("synced {}: {} change(s) in taskz, {} in the file", path, to_taskz, to_file).green()),
+    }
+    Ok(())
+}
+
+fn clear_tasks() -> io::Result<()> {
+    let tasks = load_tasks()?;
+    save_tasks(&Vec::<Task>::new())?;
+    say!("{}", "all tasks cleared".green());
+    if !tasks.is_empty() {
+        let operation = Operation::Clear { tasks };
+        record_operations(std::slice::from_ref(&operation));
+        journal::record(operation)?;
+    }
+    Ok(())
+}
+
+// the help screen, printed as is or recorded as a `help` result
+const HELP: &[&str] = &[
+    "taskz - ultimate minimalistic todo list app in rust",
+    "",
+    "usage:",
+    "  taskz -i                          install the app globally",
+    "  taskz -u                          uninstall the app",
+    "  taskz add <task> [due:<date>]     add a new task (or use --due <date>)",
+    "  taskz add <task> [!high|pri:H]    add a new task with a priority (H, M or L)",
+    "  taskz add <task> [+tag|#tag]      add a new task with tags",
+    "  taskz add --under <parent> <task> add a subtask below another task",
+    "  taskz list [-a] [--sort <order>]  list tasks, sorted by created, alpha, due or urgency",
+    "  taskz list [filter]               list tasks matching a filter like 'tag:work -someday'",
+    "  taskz search <query>              search for tasks matching the query",
+    "  taskz search --all-fields <query> also search notes and annotations",
+    "  taskz find <query> [--top n]      rank tasks the way done matches them, with scores",
+    "  taskz find <query> [--min score]  only show tasks scoring at least this (0 to 1)",
+    "  taskz search --regex <pattern>    search with a regular expression instead of a filter",
+    "  taskz search --word <query>       match whole words only (add --case-sensitive to match case)",
+    "  taskz count [filter]              count the tasks matching a filter",
+    "  taskz done <task>                 mark the task as done (and archive it)",
+    "  taskz done --filter <filter>      mark every task matching a filter as done",
+    "  taskz due <task> <date|none>      set or remove a task's due date",
+    "  taskz priority <task> <level>     set a task's priority to H, M, L or none",
+    "  taskz show <task> [--archived]    show everything about one task (or a completed one)",
+    "  taskz note <task>                 edit a task's notes in $EDITOR (or pipe them in)",
+    "  taskz annotate <task> /// <text>  add a timestamped note to a task",
+    "  taskz recur <task> <rule|none>    make a task come back after it is done",
+    "  taskz tag <task> <tag>            add a tag to a task",
+    "  taskz untag <task> <tag>          remove a tag from a task",
+    "  taskz tags                        show how many tasks carry each tag",
+    "  taskz move <task> --to <project>  move a task to another project (or none)",
+    "  taskz projects                    show how many tasks each project has",
+    "  taskz block <task> --on <other>   make a task wait until another is done",
+    "  taskz unblock <task> --on <other> remove that dependency again",
+    "  taskz ready                       list tasks that are not waiting on anything",
+    "  taskz blocked                     list tasks still waiting on others",
+    "  taskz undo [n]                    undo the last n operations (default 1)",
+    "  taskz redo [n]                    redo the last n undone operations",
+    "  taskz history [n]                 show the n most recent operations",
+    "  taskz edit <old> /// <new>        edit a task",
+    "  taskz match <query> [--top n]     preview which task a query picks and why",
+    "  taskz export [filter] [--all]     print open (or --all) tasks, -o <file> to save them",
+    "  taskz import <file> [--dry-run]   add tasks from a file, skipping duplicates",
+    "  taskz sync-md <file> [--dry-run]  sync a markdown checklist and the task list both ways",
+    "  taskz clear                       clear all tasks",
+    "  taskz archive list                list completed tasks",
+    "  taskz archive search <q>          search completed tasks",
+    "  taskz archive restore <t>         move a completed task back to the list",
+    "  taskz /? | -? | -h                show this help",
+    "",
+    "pass -p <project> before the command to add to, list or pick tasks from a single project",
+    "recurrence rules: daily, weekly, monthly, yearly, weekdays, every 2 weeks, monthly on day 15",
+    "  (inline with add, join words with dashes: recur:every-2-weeks)",
+    "dates can be like 2025-06-01, oct 20, friday, tomorrow, next monday, in 3 days or +2w",
+    "filters: words, \"phrases\", -word, tag:x, project:x, pri:H, due:<date>, due.before:<date>,",
+    "  due.after:<date>, status:open|done|blocked|ready|overdue, age>7d, AND, OR, NOT, ( )",
+    "export and import take --format csv, todo.txt, md or ics (guessed from the file name on import)",
+    "  (export --group project|tag puts md checklists under headings)",
+    "import --from taskwarrior|todoist-csv|trello-json reads other tools' exports",
+    "set TASKZ_TODO_TXT=<file> to keep tasks in a todo.txt file instead",
+    "any <task> can also be given as an id like #12 to skip fuzzy matching",
+    "weak fuzzy matches ask for confirmation; pass --yes (-y) before the command to accept them",
+    "add --explain to done or edit to see how the task would be picked instead of changing it",
+    "pass --matcher legacy before the command to match with plain levenshtein distance instead",
+    "pass --json (one document) or --jsonl (one object per line) before the command for machine-readable output",
+    "",
+    "made by tra1an.com",
+];
+
+fn print_help() {
+    for line in HELP {
+        say!("{}", line);
+    }
+    output::record("help", json!({ "lines": HELP }));
+}
+
+fn take_flag(args: &mut Vec<String>, names: &[&str]) -> bool {
+    let before = args.len();
+    args.retain(|arg| !names.contains(&arg.as_str()));
+    args.len() != before
+}
+
+// removes `--name value` or `--name=value` from args and returns the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let prefix = format!("{}=", name);
+    let index = args.iter().position(|arg| arg == name || arg.starts_with(&prefix))?;
+    let arg = args.remove(index);
+    if let Some(value) = arg.strip_prefix(&prefix) {
+        return Some(value.to_string());
+    }
+    if index < args.len() {
+        Some(args.remove(index))
+    } else {
+        Some(String::new())
+    }
+}
+
+// options taken before the command, and whether they are followed by a value
+const GLOBAL_OPTIONS: [(&str, bool); 7] = [
+    ("--project", true), ("-p", true), ("--matcher", true), ("--yes", false), ("-y", false), ("--json", false), ("--jsonl", false),
+];
+
+// moves the options in front of the command out of args, so words after
+// it (descriptions, queries) are never mistaken for them
+fn take_global_options(args: &mut Vec<String>) -> Vec<String> {
+    let mut options = Vec::new();
+    while let Some(arg) = args.get(1) {
+        let Some((_, takes_value)) = GLOBAL_OPTIONS.iter().find(|(name, _)| arg == name || arg.starts_with(&format!("{}=", name))) else {
+            break;
+        };
+        let separate_value = *takes_value && !arg.contains('=');
+        options.push(args.remove(1));
+        if separate_value && args.len() > 1 {
+            options.push(args.remove(1));
+        }
+    }
+    options
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    let mut options = take_global_options(&mut args);
+    if take_flag(&mut options, &["--jsonl"]) {
+        output::set_mode(output::Mode::JsonLines);
+    } else if take_flag(&mut options, &["--json"]) {
+        output::set_mode(output::Mode::Json);
+    }
+    run(args, options);
+    output::finish();
+}
+
+fn run(mut args: Vec<String>, mut options: Vec<String>) {
+    let assume_yes = take_flag(&mut options, &["--yes", "-y"]);
+    let algorithm = match take_option(&mut options, "--matcher") {
+        Some(name) => match Algorithm::parse(&name) {
+            Some(algorithm) => Some(algorithm),
+            None => {
+                output::error("invalid_input", format!("unknown matcher \"{}\", expected smart or legacy", name));
+                return;
+            }
+        },
+        None => None,
+    };
+    let project = match take_option(&mut options, "--project").or_else(|| take_option(&mut options, "-p")) {
+        Some(name) => match parse_project(&name) {
+            Some(project) => Some(project),
+            None => {
+                output::error("invalid_input", format!("invalid project name \"{}\"", name));
+                return;
+            }
+        },
+        None => None,
+    };
+    let match_options = MatchOptions::from_env(algorithm, project.clone(), assume_yes);
+    if args.len() < 2 {
+        output::error("invalid_input", "no command provided. usage: taskz [options]");
+        return;
+    }
+    output::set_command(&args[1]);
+    match args[1].as_str() {
+        "-i" => {
+            if let Err(e) = install() {
+                output::error("io_error", format!("installation failed: {}", e));
+            }
+        },
+        "-u" => {
+            if let Err(e) = uninstall() {
+                output::error("io_error", format!("uninstallation failed: {}", e));
+            }
+        },
+        "add" => {
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide a task description");
+                return;
+            }
+            let due_option = take_option(&mut args, "--due");
+            let parent_query = take_option(&mut args, "--under");
+            let (description, mut attributes) = match extract_attributes(&args[2..]) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    output::error("invalid_input", e);
+                    return;
+                }
+            };
+            if let Some(value) = due_option {
+                match parse_due(&value) {
+                    Ok(due) => attributes.due = Some(due),
+                    Err(e) => {
+                        output::error("invalid_input", e);
+                        return;
+                    }
+                }
+            }
+            if description.is_empty() {
+                output::error("invalid_input", "please provide a task description");
+                return;
+            }
+            if attributes.project.is_none() {
+                attributes.project = project;
+            }
+            if let Err(e) = add_task(description, attributes, parent_query, &match_options) {
+                output::error("io_error", format!("failed to add task: {}", e));
+            }
+        },
+        "list" => {
+            let alphabetical = take_flag(&mut args, &["-a"]);
+            let sort = match take_option(&mut args, "--sort") {
+                Some(name) => match SortOrder::parse(&name) {
+                    Some(sort) => sort,
+                    None => {
+                        output::error("invalid_input", format!("unknown sort order \"{}\", expected created, alpha, due or urgency", name));
+                        return;
+                    }
+                },
+                None if alphabetical => SortOrder::Alphabetical,
+                None => SortOrder::Created,
+            };
+            let filter = match filter::from_args(&args[2..], today()) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    output::error("invalid_input", format!("invalid filter: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = list_tasks(sort, &filter, project.as_deref()) {
+                output::error("io_error", format!("failed to list tasks: {}", e));
+            }
+        },
+        "search" | "find" => {
+            let fuzzy = args[1] == "find" || take_flag(&mut args, &["--fuzzy"]);
+            let top = match take_option(&mut args, "--top").map(|value| value.parse::<usize>()) {
+                None => DEFAULT_FUZZY_TOP,
+                Some(Ok(n)) if n > 0 => n,
+                Some(_) => {
+                    output::error("invalid_input", "please provide a positive number for --top");
+                    return;
+                }
+            };
+            let min_score = match take_option(&mut args, "--min").map(|value| value.parse::<f64>()) {
+                None => 0.0,
+                Some(Ok(min)) if (0.0..=1.0).contains(&min) => min,
+                Some(_) => {
+                    output::error("invalid_input", "please provide a score between 0 and 1 for --min");
+                    return;
+                }
+            };
+            let all_fields = take_flag(&mut args, &["--all-fields"]);
+            let regex = take_flag(&mut args, &["--regex"]);
+            let word = take_flag(&mut args, &["--word"]);
+            let case_sensitive = take_flag(&mut args, &["--case-sensitive"]);
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide a search query");
+                return;
+            }
+            let query = args[2..].join(" ");
+            if fuzzy {
+                if let Err(e) = fuzzy_search(query, top, min_score, &match_options) {
+                    output::error("io_error", format!("failed to search tasks: {}", e));
+                }
+                return;
+            }
+            if regex || word || case_sensitive {
+                match build_pattern(&query, regex, word, case_sensitive) {
+                    Ok(pattern) => {
+                        if let Err(e) = search_pattern(&query, &pattern, all_fields, project.as_deref()) {
+                            output::error("io_error", format!("failed to search tasks: {}", e));
+                        }
+                    },
+                    Err(e) => output::error("invalid_input", e),
+                }
+                return;
+            }
+            let filter = match filter::from_args(&args[2..], today()) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    output::error("invalid_input", format!("invalid filter: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = search_tasks(&query, &filter, all_fields, project.as_deref()) {
+                output::error("io_error", format!("failed to search tasks: {}", e));
+            }
+        },
+        "count" => {
+            let filter = match filter::from_args(&args[2..], today()) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    output::error("invalid_input", format!("invalid filter: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = count_tasks(&filter, project.as_deref()) {
+                output::error("io_error", format!("failed to count tasks: {}", e));
+            }
+        },
+        "done" => {
+            let by_filter = take_flag(&mut args, &["--filter"]);
+            let explain = take_flag(&mut args, &["--explain"]);
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide the task to mark as done");
+                return;
+            }
+            if by_filter {
+                let result = filter::from_args(&args[2..], today()).map_err(|e| format!("invalid filter: {}", e));
+                match result {
+                    Ok(filter) => {
+                        if let Err(e) = mark_done_matching(&filter, &match_options) {
+                            output::error("io_error", format!("failed to mark tasks as done: {}", e));
+                        }
+                    },
+                    Err(e) => output::error("invalid_input", e),
+                }
+                return;
+            }
+            let query = args[2..].join(" ");
+            if explain {
+                if let Err(e) = explain_match(query, DEFAULT_EXPLAIN_TOP, &match_options) {
+                    output::error("io_error", format!("failed to explain match: {}", e));
+                }
+                return;
+            }
+            if let Err(e) = mark_done(query, &match_options) {
+                output::error("io_error", format!("failed to mark task as done: {}", e));
+            }
+        },
+        "due" => {
+            if args.len() < 4 {
+                output::error("invalid_input", "please provide the task and its due date");
+                return;
+            }
+            if let Err(e) = set_due(&args[2..], &match_options) {
+                output::error("io_error", format!("failed to set due date: {}", e));
+            }
+        },
+        "note" | "show" => {
+            let archived = take_flag(&mut args, &["--archived"]);
+            if args.len() < 3 {
+                output::error("invalid_input", format!("please provide the task to {}", args[1]));
+                return;
+            }
+            let query = args[2..].join(" ");
+            let result = if args[1] == "note" { edit_notes(query, &match_options) } else { show_task(query, archived, &match_options) };
+            if let Err(e) = result {
+                output::error("io_error", format!("failed to {} task: {}", args[1], e));
+            }
+        },
+        "annotate" => {
+            // `taskz annotate #12 text` or `taskz annotate <task> /// text`
+            let joined = args[2..].join(" ");
+            let parts: Option<(String, String)> = match joined.split_once("///") {
+                Some((query, text)) => Some((query.trim().to_string(), text.trim().to_string())),
+                None => args.get(2).filter(|arg| parse_task_id(arg).is_some()).map(|id| (id.clone(), args[3..].join(" "))),
+            };
+            let Some((query, text)) = parts.filter(|(query, text)| !query.is_empty() && !text.is_empty()) else {
+                output::error("invalid_input", "please provide the annotation in format: taskz annotate <task> /// <text> (or taskz annotate #id <text>)");
+                return;
+            };
+            if let Err(e) = annotate_task(query, text, &match_options) {
+                output::error("io_error", format!("failed to annotate task: {}", e));
+            }
+        },
+        "recur" => {
+            if args.len() < 4 {
+                output::error("invalid_input", "please provide the task and how often it recurs");
+                return;
+            }
+            if let Err(e) = set_recurrence(&args[2..], &match_options) {
+                output::error("io_error", format!("failed to set recurrence: {}", e));
+            }
+        },
+        "priority" => {
+            if args.len() < 4 {
+                output::error("invalid_input", "please provide the task and its priority (H, M, L or none)");
+                return;
+            }
+            let level = args[args.len() - 1].clone();
+            let priority = if level.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                match Priority::parse(&level) {
+                    Some(priority) => Some(priority),
+                    None => {
+                        output::error("invalid_input", format!("unknown priority \"{}\", expected H, M, L or none", level));
+                        return;
+                    }
+                }
+            };
+            let query = args[2..args.len() - 1].join(" ");
+            if let Err(e) = set_priority(query, priority, &match_options) {
+                output::error("io_error", format!("failed to set priority: {}", e));
+            }
+        },
+        "tag" | "untag" => {
+            let tag = args.last().and_then(|word| parse_tag(word).or_else(|| parse_tag(&format!("+{}", word))));
+            let (Some(tag), true) = (tag, args.len() >= 4) else {
+                output::error("invalid_input", format!("please provide the task and the tag, e.g. taskz {} <task> +work", args[1]));
+                return;
+            };
+            let query = args[2..args.len() - 1].join(" ");
+            if let Err(e) = tag_task(query, tag, args[1] == "untag", &match_options) {
+                output::error("io_error", format!("failed to {} task: {}", args[1], e));
+            }
+        },
+        "move" => {
+            let destination = take_option(&mut args, "--to");
+            let project = match destination.as_deref() {
+                Some("none") => None,
+                Some(name) => match parse_project(name) {
+                    Some(project) => Some(project),
+                    None => {
+                        output::error("invalid_input", format!("invalid project name \"{}\"", name));
+                        return;
+                    }
+                },
+                None => {
+                    output::error("invalid_input", "please provide the destination, e.g. taskz move <task> --to home");
+                    return;
+                }
+            };
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide the task to move");
+                return;
             }
             let query = args[2..].join(" ");
-            if let Err(e) = mark_done(query) {
-                eprintln!("{}", format!("failed to mark task as done: {}", e).red());
+            if let Err(e) = move_task(query, project, &match_options) {
+                output::error("io_error", format!("failed to move task: {}", e));
+            }
+        },
+        "projects" => {
+            if let Err(e) = list_projects() {
+                output::error("io_error", format!("failed to list projects: {}", e));
+            }
+        },
+        "block" | "unblock" => {
+            let Some(split) = args.iter().position(|arg| arg == "--on") else {
+                output::error("invalid_input", format!("please provide both tasks, e.g. taskz {} <task> --on <other task>", args[1]));
+                return;
+            };
+            let query = args[2..split].join(" ");
+            let dependency_query = args[split + 1..].join(" ");
+            if query.is_empty() || dependency_query.is_empty() {
+                output::error("invalid_input", format!("please provide both tasks, e.g. taskz {} <task> --on <other task>", args[1]));
+                return;
+            }
+            if let Err(e) = block_task(query, dependency_query, args[1] == "unblock", &match_options) {
+                output::error("io_error", format!("failed to {} task: {}", args[1], e));
+            }
+        },
+        "ready" | "blocked" => {
+            if let Err(e) = list_by_dependencies(args[1] == "blocked", project.as_deref()) {
+                output::error("io_error", format!("failed to list {} tasks: {}", args[1], e));
             }
         },
-        "undo" => {
-            if let Err(e) = undo_last() {
-                eprintln!("{}", format!("failed to undo: {}", e).red());
+        "tags" => {
+            if let Err(e) = list_tags() {
+                output::error("io_error", format!("failed to list tags: {}", e));
+            }
+        },
+        "undo" | "redo" | "history" => {
+            let default_count = if args[1] == "history" { 10 } else { 1 };
+            let count = match args.get(2).map(|s| s.parse::<usize>()) {
+                None => default_count,
+                Some(Ok(n)) if n > 0 => n,
+                Some(_) => {
+                    output::error("invalid_input", "please provide a positive number");
+                    return;
+                }
+            };
+            let result = match args[1].as_str() {
+                "undo" => undo_last(count),
+                "redo" => redo_last(count),
+                _ => show_history(count),
+            };
+            if let Err(e) = result {
+                output::error("io_error", format!("failed to {}: {}", args[1].replace("history", "show history"), e));
             }
         },
         "edit" => {
             let joined = args[2..].join(" ");
             let parts: Vec<&str> = joined.split("///").map(|s| s.trim()).collect();
             if parts.len() != 2 {
-                eprintln!("{}", "please provide the edit command in format: taskz edit <query> /// <new description>".red());
+                output::error("invalid_input", "please provide the edit command in format: taskz edit <query> /// <new description>");
+                return;
+            }
+            // only the query side is searched, the new description may hold any word
+            let mut query_words: Vec<String> = parts[0].split_whitespace().map(str::to_string).collect();
+            let explain = take_flag(&mut query_words, &["--explain"]);
+            let query = query_words.join(" ");
+            let words: Vec<String> = parts[1].split_whitespace().map(|word| word.to_string()).collect();
+            let (new_description, attributes) = match extract_attributes(&words) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    output::error("invalid_input", e);
+                    return;
+                }
+            };
+            if explain {
+                if let Err(e) = explain_match(query, DEFAULT_EXPLAIN_TOP, &match_options) {
+                    output::error("io_error", format!("failed to explain match: {}", e));
+                }
+                return;
+            }
+            if let Err(e) = edit_task(query, new_description, attributes, &match_options) {
+                output::error("io_error", format!("failed to edit task: {}", e));
+            }
+        },
+        "match" => {
+            let top = match take_option(&mut args, "--top").map(|value| value.parse::<usize>()) {
+                None => DEFAULT_EXPLAIN_TOP,
+                Some(Ok(n)) if n > 0 => n,
+                Some(_) => {
+                    output::error("invalid_input", "please provide a positive number for --top");
+                    return;
+                }
+            };
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide a query to match");
+                return;
+            }
+            let query = args[2..].join(" ");
+            if let Err(e) = explain_match(query, top, &match_options) {
+                output::error("io_error", format!("failed to explain match: {}", e));
+            }
+        },
+        "export" => {
+            let group = match take_option(&mut args, "--group").map(|name| (Group::parse(&name), name)) {
+                None => None,
+                Some((Some(group), _)) => Some(group),
+                Some((None, name)) => {
+                    output::error("invalid_input", format!("unknown grouping \"{}\", expected project or tag", name));
+                    return;
+                }
+            };
+            let format = match take_option(&mut args, "--format").map(|name| (Format::parse(&name), name)) {
+                None if group.is_some() => Format::Markdown,
+                None => Format::Csv,
+                Some((Some(format), _)) => format,
+                Some((None, name)) => {
+                    output::error("invalid_input", format!("unknown format \"{}\", expected csv, todo.txt, md or ics", name));
+                    return;
+                }
+            };
+            if !format.can_export() {
+                output::error("invalid_input", format!("{} can only be imported", format.name()));
+                return;
+            }
+            if group.is_some() && format != Format::Markdown {
+                output::error("invalid_input", "--group only works with --format md");
+                return;
+            }
+            let path = take_option(&mut args, "--output").or_else(|| take_option(&mut args, "-o"));
+            let all = take_flag(&mut args, &["--all"]);
+            let filter = match filter::from_args(&args[2..], today()) {
+                Ok(filter) => filter,
+                Err(e) => {
+                    output::error("invalid_input", format!("invalid filter: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = export_tasks(format, group, filter, all, path, project.as_deref()) {
+                output::error("io_error", format!("failed to export tasks: {}", e));
+            }
+        },
+        "import" => {
+            let format_name = take_option(&mut args, "--format").or_else(|| take_option(&mut args, "--from"));
+            let dry_run = take_flag(&mut args, &["--dry-run", "-n"]);
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide the file to import");
+                return;
+            }
+            let path = args[2..].join(" ");
+            let format = match format_name.as_deref().map(Format::parse).unwrap_or_else(|| Format::from_path(&path)) {
+                Some(format) => format,
+                None => {
+                    output::error("invalid_input", "unknown format, pass --format csv, todo.txt, md or ics, or --from taskwarrior, todoist-csv or trello-json");
+                    return;
+                }
+            };
+            if let Err(e) = import_tasks(path, format, dry_run, project.as_deref()) {
+                output::error("io_error", format!("failed to import tasks: {}", e));
+            }
+        },
+        "sync-md" => {
+            let dry_run = take_flag(&mut args, &["--dry-run", "-n"]);
+            if args.len() < 3 {
+                output::error("invalid_input", "please provide the markdown file to sync");
                 return;
             }
-            let query = parts[0].to_string();
-            let new_description = parts[1].to_string();
-            if let Err(e) = edit_task(query, new_description) {
-                eprintln!("{}", format!("failed to edit task: {}", e).red());
+            let path = args[2..].join(" ");
+            if let Err(e) = sync_markdown(path, project.as_deref(), dry_run) {
+                output::error("io_error", format!("failed to sync: {}", e));
             }
         },
         "clear" => {
             if let Err(e) = clear_tasks() {
-                eprintln!("{}", format!("failed to clear tasks: {}", e).red());
+                output::error("io_error", format!("failed to clear tasks: {}", e));
+            }
+        },
+        "archive" => {
+            let subcommand = args.get(2).map(|s| s.as_str()).unwrap_or("list");
+            match subcommand {
+                "list" => {
+                    if let Err(e) = list_archive() {
+                        output::error("io_error", format!("failed to list archive: {}", e));
+                    }
+                },
+                "search" | "restore" => {
+                    if args.len() < 4 {
+                        output::error("invalid_input", format!("please provide the task to {}", subcommand));
+                        return;
+                    }
+                    let query = args[3..].join(" ");
+                    if subcommand == "search" {
+                        if let Err(e) = search_archive(query) {
+                            output::error("io_error", format!("failed to search archive: {}", e));
+                        }
+                    } else if let Err(e) = restore_task(query, &match_options) {
+                        output::error("io_error", format!("failed to restore task: {}", e));
+                    }
+                },
+                _ => {
+                    output::error("invalid_input", "unknown archive command. usage: taskz archive [list|search|restore]");
+                }
             }
         },
         "/?" | "-?" | "-h" => {
             print_help();
         },
         _ => {
-            eprintln!("{}", "unknown command".red());
+            output::error("invalid_input", "unknown command");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: u32, parent: Option<u32>, depends_on: &[u32]) -> Task {
+        let mut task = Task::new(id, format!("task {}", id));
+        task.parent = parent;
+        task.depends_on = depends_on.to_vec();
+        task
+    }
+
+    #[test]
+    fn cycles_are_detected_through_other_tasks() {
+        let tasks = [task(1, None, &[2]), task(2, Some(1), &[3]), task(3, Some(2), &[])];
+        assert!(depends_on(&tasks, 1, 3));
+        assert!(!depends_on(&tasks, 3, 1));
+        assert!(descends_from(&tasks, 3, 1));
+        assert!(!descends_from(&tasks, 1, 3));
+    }
+
+    #[test]
+    fn looping_parents_do_not_recurse_forever() {
+        let tasks = [task(1, Some(2), &[]), task(2, Some(1), &[]), task(3, Some(2), &[])];
+        assert_eq!(open_descendants(&tasks, 1), [3, 2]);
+        assert!(!descends_from(&tasks, 1, 4));
+    }
+
+    #[test]
+    fn word_boundaries_only_next_to_word_characters() {
+        let pattern = |query: &str| build_pattern(query, false, true, false).unwrap();
+        assert!(pattern("c++").is_match("learn C++ today"));
+        assert!(!pattern("c++").is_match("abc++"));
+        assert!(pattern(".net").is_match("port to .net"));
+        assert!(pattern("cat").is_match("feed the cat"));
+        assert!(!pattern("cat").is_match("category list"));
+    }
+
+    // the only test touching the data directory, which it moves to a fresh one
+    #[test]
+    fn items_linked_by_description_keep_their_id() {
+        let home = env::temp_dir().join(format!("taskz-test-{}", std::process::id()));
+        env::set_var("HOME", &home);
+        env::remove_var("TASKZ_TODO_TXT");
+        save_tasks(&[Task::new(1, "feed the cat".to_string())]).unwrap();
+        let file = home.join("TODO.md");
+        let path = file.to_string_lossy().into_owned();
+        fs::write(&file, "- [ ] feed the cat\n").unwrap();
+        sync_markdown(path.clone(), None, false).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "- [ ] feed the cat <!-- #1 -->\n");
+        let mut tasks = load_tasks().unwrap();
+        tasks[0].description = "feed the cat twice".to_string();
+        save_tasks(&tasks).unwrap();
+        sync_markdown(path, None, false).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "- [ ] feed the cat twice <!-- #1 -->\n");
+        assert_eq!(load_tasks().unwrap().len(), 1);
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
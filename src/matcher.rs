@@ -0,0 +1,182 @@
+use strsim::{jaro_winkler, levenshtein};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    // substring, prefix, per-token, jaro-winkler and word-order-insensitive scoring combined
+    Smart,
+    // plain levenshtein over the whole lowercased strings
+    Legacy,
+}
+
+impl Algorithm {
+    pub fn parse(name: &str) -> Option<Algorithm> {
+        match name.to_lowercase().as_str() {
+            "smart" => Some(Algorithm::Smart),
+            "legacy" | "levenshtein" => Some(Algorithm::Legacy),
+            _ => None,
+        }
+    }
+}
+
+// weight 0.0 marks a component that does not feed the weighted blend
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub name: &'static str,
+    pub value: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub total: f64,
+    pub blend: f64,
+    pub distance: usize,
+    pub components: Vec<Component>,
+}
+
+const PHRASE_AT_START: f64 = 1.0;
+const PHRASE_AT_WORD: f64 = 0.9;
+const PHRASE_ANYWHERE: f64 = 0.8;
+const PREFIX_TOKEN: f64 = 0.9;
+const TOKENS_WEIGHT: f64 = 0.45;
+const JARO_WINKLER_WEIGHT: f64 = 0.2;
+const WORD_ORDER_WEIGHT: f64 = 0.2;
+const LEVENSHTEIN_WEIGHT: f64 = 0.15;
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|token| !token.is_empty()).map(|token| token.to_string()).collect()
+}
+
+fn normalized_levenshtein(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+// how well the query appears verbatim inside the description; a hit that
+// stops or starts inside a word only counts for the share of the words it covers
+fn phrase_score(description: &str, query: &str) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    description.match_indices(query).map(|(start, _)| {
+        let end = start + query.len();
+        let base = if start == 0 {
+            PHRASE_AT_START
+        } else if description[..start].chars().next_back().is_some_and(|c| !c.is_alphanumeric()) {
+            PHRASE_AT_WORD
+        } else {
+            PHRASE_ANYWHERE
+        };
+        // widen the hit to the whole words it touches
+        let from = description[..start].char_indices().rev().find(|(_, c)| !c.is_alphanumeric()).map_or(0, |(i, c)| i + c.len_utf8());
+        let to = description[end..].find(|c: char| !c.is_alphanumeric()).map_or(description.len(), |i| end + i);
+        if from == start && to == end {
+            return base;
+        }
+        base * query.chars().count() as f64 / description[from..to].chars().count() as f64
+    }).fold(0.0, f64::max)
+}
+
+fn token_similarity(description_token: &str, query_token: &str) -> f64 {
+    if description_token == query_token {
+        return 1.0;
+    }
+    // a prefix is worth the share of the longer token it covers
+    let (short, long) = if description_token.len() < query_token.len() { (description_token, query_token) } else { (query_token, description_token) };
+    let prefix = if long.starts_with(short) { PREFIX_TOKEN * short.chars().count() as f64 / long.chars().count() as f64 } else { 0.0 };
+    prefix.max(normalized_levenshtein(description_token, query_token)).max(jaro_winkler(description_token, query_token))
+}
+
+// average over query tokens of the best matching description token
+fn token_score(description_tokens: &[String], query_tokens: &[String]) -> f64 {
+    if query_tokens.is_empty() || description_tokens.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = query_tokens.iter().map(|query_token| {
+        description_tokens.iter().map(|token| token_similarity(token, query_token)).fold(0.0, f64::max)
+    }).sum();
+    total / query_tokens.len() as f64
+}
+
+fn sorted_tokens(tokens: &[String]) -> String {
+    let mut sorted = tokens.to_vec();
+    sorted.sort();
+    sorted.join(" ")
+}
+
+// similarity between 0.0 (nothing in common) and 1.0 (identical)
+pub fn score(description: &str, query: &str, algorithm: Algorithm) -> f64 {
+    explain(description, query, algorithm).total
+}
+
+pub fn explain(description: &str, query: &str, algorithm: Algorithm) -> Explanation {
+    let description = description.to_lowercase();
+    let query = query.trim().to_lowercase();
+    let distance = levenshtein(&description, &query);
+    let whole = normalized_levenshtein(&description, &query);
+    if algorithm == Algorithm::Legacy {
+        return Explanation {
+            total: whole,
+            blend: whole,
+            distance,
+            components: vec![Component { name: "levenshtein", value: whole, weight: 1.0 }],
+        };
+    }
+    let description_tokens = tokenize(&description);
+    let query_tokens = tokenize(&query);
+    let phrase = phrase_score(&description, &query);
+    let components = vec![
+        Component { name: "phrase", value: phrase, weight: 0.0 },
+        Component { name: "tokens", value: token_score(&description_tokens, &query_tokens), weight: TOKENS_WEIGHT },
+        Component { name: "jaro-winkler", value: jaro_winkler(&description, &query), weight: JARO_WINKLER_WEIGHT },
+        Component { name: "word-order", value: normalized_levenshtein(&sorted_tokens(&description_tokens), &sorted_tokens(&query_tokens)), weight: WORD_ORDER_WEIGHT },
+        Component { name: "levenshtein", value: whole, weight: LEVENSHTEIN_WEIGHT },
+    ];
+    let blend: f64 = components.iter().map(|component| component.value * component.weight).sum();
+    // a verbatim phrase hit is trusted on its own, everything else is blended
+    Explanation { total: blend.max(phrase), blend, distance, components }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smart(description: &str, query: &str) -> f64 {
+        score(description, query, Algorithm::Smart)
+    }
+
+    #[test]
+    fn whole_words_score_full_marks() {
+        assert_eq!(smart("feed the cat", "feed the"), PHRASE_AT_START);
+        assert_eq!(smart("feed the cat", "cat"), PHRASE_AT_WORD);
+    }
+
+    #[test]
+    fn short_prefixes_are_not_trusted_on_their_own() {
+        assert!(smart("feed my cat", "f") < 0.6);
+        assert!(phrase_score("feed my cat", "f") < 0.3);
+    }
+
+    #[test]
+    fn whole_token_beats_a_longer_word_starting_with_it() {
+        assert!(smart("feed the cat", "cat") > smart("update category list", "cat"));
+        assert!(token_similarity("category", "cat") < token_similarity("cat", "cat"));
+    }
+
+    #[test]
+    fn words_split_by_non_ascii_separators() {
+        assert_eq!(phrase_score("call mom—about dinner", "about"), PHRASE_AT_WORD);
+        let partial = phrase_score("call mom—about dinner", "abo");
+        assert!(partial > 0.0 && partial < PHRASE_AT_WORD);
+        assert!(smart("déjà—vu", "vu") > 0.6);
+    }
+
+    #[test]
+    fn typed_out_phrase_covers_most_of_the_last_word() {
+        let partial = phrase_score("feed my cat", "feed my c");
+        assert!(partial > 0.8 && partial < PHRASE_AT_START);
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use colored::Colorize;
+use serde_json::{json, Value};
+use crate::Task;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Human,
+    // one document printed once the command is done
+    Json,
+    // one object per line, printed as things happen
+    JsonLines,
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+static COMMAND: OnceLock<String> = OnceLock::new();
+static RESULTS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+static ERRORS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+pub fn set_mode(mode: Mode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn set_command(command: &str) {
+    let _ = COMMAND.set(command.to_string());
+}
+
+pub fn is_human() -> bool {
+    MODE.get().is_none_or(|mode| *mode == Mode::Human)
+}
+
+fn push(list: &Mutex<Vec<Value>>, value: Value) {
+    match MODE.get() {
+        Some(Mode::JsonLines) => println!("{}", value),
+        Some(Mode::Json) => {
+            if let Ok(mut list) = list.lock() {
+                list.push(value);
+            }
+        },
+        _ => {},
+    }
+}
+
+// a structured result like {"type": "count", "count": 3}; ignored in human mode
+pub fn record(kind: &str, fields: Value) {
+    let mut object = json!({ "type": kind });
+    if let (Some(object), Value::Object(fields)) = (object.as_object_mut(), fields) {
+        object.extend(fields);
+    }
+    push(&RESULTS, object);
+}
+
+// {"type": "added", "task": {...}}
+pub fn task(kind: &str, task: &Task) {
+    record(kind, json!({ "task": task }));
+}
+
+fn failure(code: &str, message: &str) {
+    let value = json!({ "type": "error", "code": code, "message": message });
+    push(&ERRORS, value);
+}
+
+// errors from parsing the command line or failed reads and writes, printed to stderr
+pub fn error(code: &str, message: impl fmt::Display) {
+    let message = message.to_string();
+    if is_human() {
+        eprintln!("{}", message.red());
+    }
+    failure(code, &message);
+}
+
+// a command that ran but could not do what was asked, like an unmatched query
+pub fn problem(code: &str, message: impl fmt::Display) {
+    let message = message.to_string();
+    if is_human() {
+        println!("{}", message.red());
+    }
+    failure(code, &message);
+}
+
+// prints the --json document with everything the command recorded
+pub fn finish() {
+    if MODE.get() != Some(&Mode::Json) {
+        return;
+    }
+    let take = |list: &Mutex<Vec<Value>>| list.lock().map(|mut list| std::mem::take(&mut *list)).unwrap_or_default();
+    let errors = take(&ERRORS);
+    let document = json!({
+        "command": COMMAND.get(),
+        "ok": errors.is_empty(),
+        "results": take(&RESULTS),
+        "errors": errors,
+    });
+    println!("{}", serde_json::to_string_pretty(&document).unwrap_or_default());
+}